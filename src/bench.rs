@@ -0,0 +1,292 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::game::{init_game_from_code, Game, GameStatus};
+use crate::game_code::GameCode;
+use crate::solver::{bomb_probabilities, deduce, Reason};
+
+// how one game played by the bot went
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BotGame {
+    pub won: bool,
+    pub guesses: usize,
+    pub three_bv: usize,
+}
+
+// plays the game to the end with the moves of a player: flags the proven bombs,
+// chords the numbers whose bombs are all flagged, reveals the other proven safe cells,
+// and when nothing is proven reveals the unknown cell with the lowest chance of a bomb
+pub fn play_bot(game: &mut Game) -> BotGame {
+    let mut guesses = 0;
+    while game.status == GameStatus::OnGoing {
+        let deduction = deduce(game);
+        let mut changed = false;
+        for &cell in &deduction.bomb_cells {
+            changed |= !game.set_flag(cell, true).is_empty();
+        }
+        for explanation in deduction.explanations.iter().filter(|e| !e.is_bomb) {
+            let changes = match explanation.reason {
+                Reason::NumberIsFlagged(number_cell) => game.chord(number_cell),
+                _ => game.reveal(explanation.cell),
+            };
+            changed |= !changes.is_empty();
+            if game.status != GameStatus::OnGoing {
+                break;
+            }
+        }
+        if changed {
+            continue;
+        }
+
+        let (cell, probability) = match safest_cell(game) {
+            Some(safest) => safest,
+            None => break,
+        };
+        // the first click of a fresh board is kept safe by the first click rule
+        if probability > 0.0 && game.bombs_placed() {
+            guesses += 1;
+        }
+        if game.reveal(cell).is_empty() {
+            break;
+        }
+    }
+
+    BotGame {
+        won: game.status == GameStatus::Won,
+        guesses,
+        three_bv: three_bv(game),
+    }
+}
+
+// the unknown cell with the lowest chance of a bomb, the first unknown cell if it can not be counted
+fn safest_cell(game: &Game) -> Option<((usize, usize), f64)> {
+    let mut safest: Option<((usize, usize), f64)> = None;
+    match bomb_probabilities(game) {
+        Some(probabilities) => {
+            for (row, cells) in probabilities.iter().enumerate() {
+                for (column, probability) in cells.iter().enumerate() {
+                    if let Some(probability) = *probability {
+                        if safest.is_none_or(|(_, lowest)| probability < lowest) {
+                            safest = Some(((row, column), probability));
+                        }
+                    }
+                }
+            }
+        }
+        None => {
+            for (row, cells) in game.cells.iter().enumerate() {
+                for (column, cell) in cells.iter().enumerate() {
+                    if safest.is_none() && !cell.is_discovered && !cell.is_flagged {
+                        safest = Some(((row, column), 1.0));
+                    }
+                }
+            }
+        }
+    }
+    safest
+}
+
+// the least number of clicks that clear the board: one for every empty area,
+// and one for every safe cell that is not next to an empty cell
+pub fn three_bv(game: &Game) -> usize {
+    if !game.bombs_placed() {
+        return 0;
+    }
+    let is_empty = |(row, column): (usize, usize)| {
+        let cell = &game.cells[row][column];
+        !cell.is_bomb && cell.number_of_adjusted_bombs == 0
+    };
+
+    let mut counted = vec![vec![false; game.size.1]; game.size.0];
+    let mut clicks = 0;
+    for row in 0..game.size.0 {
+        for column in 0..game.size.1 {
+            if counted[row][column] || !is_empty((row, column)) {
+                continue;
+            }
+            // the whole area opens with one click, with the numbers around it
+            clicks += 1;
+            counted[row][column] = true;
+            let mut stack = vec![(row, column)];
+            while let Some(index) = stack.pop() {
+                for neighbour in game.get_adjusted_indices(index) {
+                    if !counted[neighbour.0][neighbour.1] {
+                        counted[neighbour.0][neighbour.1] = true;
+                        if is_empty(neighbour) {
+                            stack.push(neighbour);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    for (row, cells) in game.cells.iter().enumerate() {
+        for (column, cell) in cells.iter().enumerate() {
+            if !cell.is_bomb && !counted[row][column] {
+                clicks += 1;
+            }
+        }
+    }
+    clicks
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct BenchTotals {
+    pub games: usize,
+    pub won: usize,
+    pub guesses: usize,
+    pub three_bv: usize,
+}
+
+impl BenchTotals {
+    fn add(&mut self, bot_game: &BotGame) {
+        self.games += 1;
+        self.won += bot_game.won as usize;
+        self.guesses += bot_game.guesses;
+        self.three_bv += bot_game.three_bv;
+    }
+
+    fn merge(&mut self, other: &BenchTotals) {
+        self.games += other.games;
+        self.won += other.won;
+        self.guesses += other.guesses;
+        self.three_bv += other.three_bv;
+    }
+}
+
+pub struct BenchReport {
+    pub totals: BenchTotals,
+    pub threads: usize,
+    pub elapsed: Duration,
+}
+
+// plays the games with the seeds after the one of the game code, split between the threads
+pub fn run_bench(
+    game_code: &GameCode,
+    games: usize,
+    threads: usize,
+) -> Result<BenchReport, String> {
+    let threads = threads.clamp(1, games.max(1));
+    let start = Instant::now();
+    let results: Vec<Result<BenchTotals, String>> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|thread_index| {
+                scope.spawn(move || {
+                    let mut totals = BenchTotals::default();
+                    for game_index in (thread_index..games).step_by(threads) {
+                        let mut game_code = game_code.clone();
+                        game_code.seed = game_code.seed.wrapping_add(game_index as u64);
+                        let mut game = init_game_from_code(&game_code)?;
+                        totals.add(&play_bot(&mut game));
+                    }
+                    Ok(totals)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+
+    let mut totals = BenchTotals::default();
+    for result in results {
+        totals.merge(&result?);
+    }
+    Ok(BenchReport {
+        totals,
+        threads,
+        elapsed: start.elapsed(),
+    })
+}
+
+pub fn format_bench_report(report: &BenchReport) -> String {
+    let totals = &report.totals;
+    let per_game = |value: usize| value as f64 / totals.games.max(1) as f64;
+    let seconds = report.elapsed.as_secs_f64();
+    let mut text = String::new();
+    text.push_str(&format!(
+        "games: {}, won: {} ({:.1}%)\n",
+        totals.games,
+        totals.won,
+        100.0 * per_game(totals.won)
+    ));
+    text.push_str(&format!(
+        "guesses per game: {:.2}\n",
+        per_game(totals.guesses)
+    ));
+    text.push_str(&format!("average 3BV: {:.1}\n", per_game(totals.three_bv)));
+    text.push_str(&format!(
+        "throughput: {:.0} games/s ({:.1}s, threads: {})\n",
+        if seconds > 0.0 {
+            totals.games as f64 / seconds
+        } else {
+            0.0
+        },
+        seconds,
+        report.threads
+    ));
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{init_game_from_layout, FirstClickRule, Neighbourhood, Topology};
+
+    #[test]
+    fn three_bv_counts_areas_and_lonely_numbers() {
+        // B 1 . .
+        // 1 1 . .
+        // . . 1 1
+        // . . 1 B
+        let game = init_game_from_layout((4, 4), &[(0, 0), (3, 3)]);
+        assert_eq!(three_bv(&game), 1);
+
+        // B 2 B
+        // 1 2 1
+        let game = init_game_from_layout((2, 3), &[(0, 0), (0, 2)]);
+        assert_eq!(three_bv(&game), 4);
+    }
+
+    #[test]
+    fn bot_wins_no_guess_games_without_guessing() {
+        for seed in 0..5 {
+            let game_code = GameCode {
+                size: (9, 9),
+                number_of_bombs: 10,
+                first_click_rule: FirstClickRule::Zero,
+                no_guess: true,
+                topology: Topology::Square,
+                neighbourhood: Neighbourhood::Standard,
+                max_bombs_per_cell: 1,
+                wrap: false,
+                seed,
+            };
+            let mut game = init_game_from_code(&game_code).unwrap();
+            let bot_game = play_bot(&mut game);
+            assert!(bot_game.won);
+            assert_eq!(bot_game.guesses, 0);
+            assert!(bot_game.three_bv > 0);
+        }
+    }
+
+    #[test]
+    fn bench_plays_every_game() {
+        let game_code = GameCode {
+            size: (8, 8),
+            number_of_bombs: 10,
+            first_click_rule: FirstClickRule::Safe,
+            no_guess: false,
+            topology: Topology::Square,
+            neighbourhood: Neighbourhood::Standard,
+            max_bombs_per_cell: 1,
+            wrap: false,
+            seed: 1,
+        };
+        let report = run_bench(&game_code, 20, 3).unwrap();
+        assert_eq!(report.totals.games, 20);
+        assert_eq!(report.threads, 3);
+        assert!(report.totals.won > 0);
+    }
+}
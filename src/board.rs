@@ -1,73 +1,91 @@
 use std::cmp::PartialEq;
 use std::io::{Result, Stdout};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use crossterm::{
-    cursor::MoveTo,
-    queue,
-    terminal::{Clear, ClearType},
-};
+use unicode_width::UnicodeWidthChar;
 
-use rand::Rng;
+use minesweeper::game::{FirstClickRule, Game, GameStatus, Topology};
+use minesweeper::history::History;
+use minesweeper::replay::{final_cells, perform, Action, Move, Replay, REPLAY_VERSION};
+use minesweeper::save::{load_game, save_game, SavedGame};
+use minesweeper::solver::{bomb_probabilities, explain};
 
-use crate::theme::{get_theme, rotate_theme_color, rotate_theme_name, Theme};
-
-#[derive(Clone)]
-pub struct Cell {
-    pub is_bomb: bool,
-    pub number_of_adjusted_bombs: u8,
-
-    pub is_flagged: bool,
-    pub is_discovered: bool,
-}
-
-pub fn init_blank_cell() -> Cell {
-    Cell {
-        is_bomb: false,
-        number_of_adjusted_bombs: 0,
-        is_flagged: false,
-        is_discovered: false,
-    }
-}
-
-impl Cell {
-    pub fn content_to_show(&self, theme: &Theme, selected: bool) -> String {
-        if self.is_discovered {
-            if self.is_bomb {
-                return theme.bomb.clone();
-            } else {
-                if self.number_of_adjusted_bombs == 0 {
-                    return theme.empty.clone();
-                } else {
-                    return theme
-                        .format_number_of_adjusted_bombs(self.number_of_adjusted_bombs, selected);
-                }
-            }
-        } else {
-            if self.is_flagged {
-                return theme.flag.clone();
-            } else {
-                return theme.unknown.clone();
-            }
-        }
-    }
-}
+use crate::keys::KeyBindings;
+use crate::screen::{init_screen, Frame, Screen};
+use crate::theme::{load_theme, rotate_theme_color, rotate_theme_name, Theme};
 
+// the terminal front-end of a game
 pub struct Board {
+    pub game: Game,
     pub theme: Theme,
-    pub size: (usize, usize),
-    pub cells: Vec<Vec<Cell>>,
-    pub number_of_bombs: usize,
-    pub remaining_flags: usize,
     selected_cell: Option<(usize, usize)>,
     need_to_draw: bool,
     pub delay_before_draw: Option<Duration>,
     pub game_completion_status: GameCompletionStatus,
     game_end_animation_step: usize,
     highlighted_cell: Vec<(usize, usize)>,
+    // the clock runs from the first reveal until the game is decided
+    clock_started_at: Option<Instant>,
+    elapsed_before_clock_start: Duration,
+    drawn_seconds: u64,
+    // the part of a large board that fits the terminal, from its first row and column
+    viewport_origin: (usize, usize),
+    terminal_size: Option<(usize, usize)>,
+    screen: Screen,
+    history: History,
+    // the moves since the start, or since the saved game it was resumed from
+    recorded_moves: Vec<Move>,
+    resumed_from: Option<SavedGame>,
+    // an extra line below the status, like the replay controls
+    pub footer: Option<String>,
+    // the chance of a bomb in each unknown cell, shown as colours
+    show_bomb_probabilities: bool,
+    // the chances of the current game, worked out again after a move
+    bomb_probabilities: Probabilities,
+    pub key_bindings: KeyBindings,
+    // hides the board and stops the clock, the keys only close it
+    overlay: Option<Overlay>,
+    clock_stopped_by_overlay: bool,
+    pub chord_mode: ChordMode,
+    // the left and right mouse buttons that are held, both together chord
+    mouse_buttons_held: (bool, bool),
+    // the cell under a single button, it is clicked when the button goes up
+    mouse_pressed_cell: Option<(usize, usize)>,
+}
+
+// classic: a chord only opens the neighbours of a number with as many flags around it,
+// smart: it also flags the neighbours when they can only be bombs, and a click on a number chords
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ChordMode {
+    Classic,
+    Smart,
 }
 
-#[derive(PartialEq)]
+pub fn get_chord_mode(mode_name: &str) -> Option<ChordMode> {
+    match mode_name {
+        "classic" => Some(ChordMode::Classic),
+        "smart" => Some(ChordMode::Smart),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Overlay {
+    Help,
+    Pause,
+}
+
+// the cached chance of a bomb in every unknown cell
+#[derive(PartialEq, Debug)]
+pub enum Probabilities {
+    // the game changed since they were worked out
+    Stale,
+    // the flags do not fit the numbers, or a cell can hold several bombs
+    Unavailable,
+    Ready(Vec<Vec<Option<f64>>>),
+}
+
+#[derive(PartialEq, Debug)]
 pub enum GameCompletionStatus {
     GameIsOnGoing,
     WinAnimation,
@@ -76,84 +94,423 @@ pub enum GameCompletionStatus {
     CompletedAsLost,
 }
 
-pub fn init_random_game(size: (usize, usize), bomb_percentage: f32, theme: Theme) -> Board {
-    let mut game_board = Board {
+pub fn init_board(game: Game, theme: Theme) -> Board {
+    Board {
+        game,
         theme,
-        size,
-        cells: vec![vec![init_blank_cell(); size.1]; size.0],
-        number_of_bombs: 0,
-        remaining_flags: 0,
         selected_cell: None,
         need_to_draw: true,
         delay_before_draw: None,
         game_completion_status: GameCompletionStatus::GameIsOnGoing,
         game_end_animation_step: 0,
         highlighted_cell: Vec::new(),
-    };
+        clock_started_at: None,
+        elapsed_before_clock_start: Duration::ZERO,
+        drawn_seconds: 0,
+        viewport_origin: (0, 0),
+        terminal_size: None,
+        screen: init_screen(),
+        history: History::default(),
+        recorded_moves: Vec::new(),
+        resumed_from: None,
+        footer: None,
+        show_bomb_probabilities: false,
+        bomb_probabilities: Probabilities::Stale,
+        key_bindings: KeyBindings::default(),
+        overlay: None,
+        clock_stopped_by_overlay: false,
+        chord_mode: ChordMode::Smart,
+        mouse_buttons_held: (false, false),
+        mouse_pressed_cell: None,
+    }
+}
+
+pub fn init_board_from_saved_game(saved_game: &SavedGame) -> std::result::Result<Board, String> {
+    let game = load_game(saved_game)?;
+    let theme = load_theme(&saved_game.theme)
+        .map_err(|e| format!("could not load the theme of the saved game: {}", e))?;
+    let mut game_board = init_board(game, theme);
+    game_board.elapsed_before_clock_start = Duration::from_millis(saved_game.elapsed_milliseconds);
+    game_board.drawn_seconds = game_board.elapsed().as_secs();
+    game_board.resumed_from = Some(saved_game.clone());
+    Ok(game_board)
+}
 
-    // generate bombs
-    game_board.number_of_bombs = ((((size.0 * size.1) as f32) * bomb_percentage).round()) as usize;
-    game_board.remaining_flags = game_board.number_of_bombs;
-    let mut remaning_bombs = game_board.number_of_bombs;
-    let mut random = rand::thread_rng();
-    while remaning_bombs > 0 {
-        let x = random.gen_range(0..size.0);
-        let y = random.gen_range(0..size.1);
-        if !game_board.cells[x][y].is_bomb {
-            game_board.cells[x][y].is_bomb = true;
-            remaning_bombs -= 1;
+// the end of the cells that fit in the available length, at least one cell is shown
+fn visible_cells_from(
+    first: usize,
+    number_of_cells: usize,
+    available: usize,
+    cell_length: impl Fn(usize) -> usize,
+    closing_border: usize,
+) -> usize {
+    let mut end = first;
+    let mut used = 0;
+    while end < number_of_cells {
+        let mut length = cell_length(end);
+        if end + 1 == number_of_cells {
+            length += closing_border;
         }
+        if used + length > available && end > first {
+            break;
+        }
+        used += length;
+        end += 1;
+    }
+    end
+}
+
+// the first cell that still shows the last cell
+fn last_first_cell(
+    number_of_cells: usize,
+    available: usize,
+    cell_length: impl Fn(usize) -> usize,
+    closing_border: usize,
+) -> usize {
+    let mut first = number_of_cells;
+    let mut used = closing_border;
+    while first > 0 {
+        let length = cell_length(first - 1);
+        if used + length > available && first < number_of_cells {
+            break;
+        }
+        used += length;
+        first -= 1;
     }
-    game_board.fill_numbers();
+    first
+}
 
-    // make a starting point
-    game_board.hint();
+// the pieces of a line of text that fit in the columns, the characters that take no column
+// join the piece before them
+fn text_pieces(text: &str, columns: usize) -> Vec<String> {
+    let mut pieces: Vec<String> = Vec::new();
+    let mut used = 0;
+    for c in text.chars() {
+        match c.width() {
+            Some(0) => {
+                if let Some(piece) = pieces.last_mut() {
+                    piece.push(c);
+                }
+            }
+            Some(width) if used + width <= columns => {
+                used += width;
+                pieces.push(c.to_string());
+            }
+            Some(_) => break,
+            None => {}
+        }
+    }
+    pieces
+}
 
-    game_board
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            (seconds / 60) % 60,
+            seconds % 60
+        )
+    } else {
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
 }
 
 impl Board {
-    fn fill_numbers(&mut self) {
-        for row in 0..self.size.0 {
-            for column in 0..self.size.1 {
-                for index in self.get_adjusted_indices((row, column)) {
-                    if self.cells[index.0][index.1].is_bomb {
-                        self.cells[row][column].number_of_adjusted_bombs += 1;
-                    }
-                }
+    pub fn elapsed(&self) -> Duration {
+        match self.clock_started_at {
+            Some(started_at) => self.elapsed_before_clock_start + started_at.elapsed(),
+            None => self.elapsed_before_clock_start,
+        }
+    }
+
+    fn stop_clock(&mut self) {
+        self.elapsed_before_clock_start = self.elapsed();
+        self.clock_started_at = None;
+    }
+
+    // how long the input can be waited for before the clock needs a redraw
+    pub fn time_until_next_second(&self) -> Option<Duration> {
+        self.clock_started_at?;
+        let elapsed = self.elapsed();
+        Some(Duration::from_secs(elapsed.as_secs() + 1) - elapsed)
+    }
+
+    pub fn update_clock(&mut self) {
+        let seconds = self.elapsed().as_secs();
+        if seconds != self.drawn_seconds {
+            self.drawn_seconds = seconds;
+            self.need_to_draw = true;
+        }
+    }
+
+    pub fn resize(&mut self, terminal_rows: usize, terminal_columns: usize) {
+        self.terminal_size = Some((terminal_rows, terminal_columns));
+        self.keep_in_view();
+        self.screen.invalidate();
+        self.need_to_draw = true;
+    }
+
+    fn is_hex(&self) -> bool {
+        self.game.game_code.topology == Topology::Hex
+    }
+
+    // the borders of a square grid do not fit the shifted rows of a hex board,
+    // a board that wraps always has the outer border to show its edges go on
+    fn has_outer_border(&self) -> bool {
+        (self.theme.outer_border_enabled || self.game.game_code.wrap) && !self.is_hex()
+    }
+
+    fn has_row_border(&self, row: usize) -> bool {
+        (row == 0 && self.has_outer_border())
+            || (row != 0 && self.theme.inner_border_row_enabled && !self.is_hex())
+    }
+
+    fn has_column_border(&self, column: usize) -> bool {
+        (column == 0 && self.has_outer_border())
+            || (column != 0 && self.theme.inner_border_column_enabled && !self.is_hex())
+    }
+
+    // the height of a row with the border above it
+    fn row_height(&self, row: usize) -> usize {
+        usize::from(self.has_row_border(row)) + 1
+    }
+
+    // the columns for the content of a cell, two when the numbers can go above 9
+    // or the cells can hold several bombs, which are shown with their count like F2
+    fn content_width(&self) -> usize {
+        let width = self.game.max_number().to_string().len();
+        if self.game.game_code.max_bombs_per_cell > 1 {
+            width.max(2)
+        } else {
+            width
+        }
+    }
+
+    // the width of a column with the border on its left
+    fn column_width(&self, column: usize) -> usize {
+        let padding = if self.theme.cell_horizontal_padding_enabled {
+            2
+        } else {
+            0
+        };
+        // the cells of a hex board have a gap on their right, the odd rows start in the middle of it
+        usize::from(self.has_column_border(column))
+            + self.content_width()
+            + padding
+            + usize::from(self.is_hex())
+    }
+
+    // the odd rows of a hex board are shifted right by half a cell
+    fn row_offset(&self, row: usize) -> usize {
+        if self.is_hex() && row % 2 == 1 {
+            self.column_width(0) / 2
+        } else {
+            0
+        }
+    }
+
+    // the board lines leave one line for the status, one for the footer and one for the cursor
+    fn available_size(&self) -> (usize, usize) {
+        match self.terminal_size {
+            Some((rows, columns)) => (rows.saturating_sub(3), columns),
+            None => (usize::MAX, usize::MAX),
+        }
+    }
+
+    fn closing_border(&self) -> usize {
+        usize::from(self.has_outer_border())
+    }
+
+    // the columns left for the cells, after the shift of the odd rows
+    fn available_columns(&self) -> usize {
+        let shift = if self.is_hex() && self.game.size.0 > 1 {
+            self.row_offset(1)
+        } else {
+            0
+        };
+        self.available_size().1.saturating_sub(shift)
+    }
+
+    // the visible rows and columns, as ranges of the board
+    fn viewport(&self) -> ((usize, usize), (usize, usize)) {
+        let available_rows = self.available_size().0;
+        let available_columns = self.available_columns();
+        let row_end = visible_cells_from(
+            self.viewport_origin.0,
+            self.game.size.0,
+            available_rows,
+            |row| self.row_height(row),
+            self.closing_border(),
+        );
+        let column_end = visible_cells_from(
+            self.viewport_origin.1,
+            self.game.size.1,
+            available_columns,
+            |column| self.column_width(column),
+            self.closing_border(),
+        );
+        (
+            (self.viewport_origin.0, row_end),
+            (self.viewport_origin.1, column_end),
+        )
+    }
+
+    fn last_viewport_origin(&self) -> (usize, usize) {
+        let available_rows = self.available_size().0;
+        let available_columns = self.available_columns();
+        (
+            last_first_cell(
+                self.game.size.0,
+                available_rows,
+                |row| self.row_height(row),
+                self.closing_border(),
+            ),
+            last_first_cell(
+                self.game.size.1,
+                available_columns,
+                |column| self.column_width(column),
+                self.closing_border(),
+            ),
+        )
+    }
+
+    // moves the viewport by some cells, without going past the board
+    pub fn pan(&mut self, dr: isize, dc: isize) {
+        let last_origin = self.last_viewport_origin();
+        let origin = (
+            self.viewport_origin
+                .0
+                .saturating_add_signed(dr)
+                .min(last_origin.0),
+            self.viewport_origin
+                .1
+                .saturating_add_signed(dc)
+                .min(last_origin.1),
+        );
+        if origin != self.viewport_origin {
+            self.viewport_origin = origin;
+            self.need_to_draw = true;
+        }
+    }
+
+    // scrolls just enough to show the selected cell
+    fn keep_in_view(&mut self) {
+        let last_origin = self.last_viewport_origin();
+        self.viewport_origin.0 = self.viewport_origin.0.min(last_origin.0);
+        self.viewport_origin.1 = self.viewport_origin.1.min(last_origin.1);
+
+        if let Some((row, column)) = self.selected_cell {
+            if row < self.viewport_origin.0 {
+                self.viewport_origin.0 = row;
+            }
+            while row >= self.viewport().0 .1 {
+                self.viewport_origin.0 += 1;
+            }
+            if column < self.viewport_origin.1 {
+                self.viewport_origin.1 = column;
+            }
+            while column >= self.viewport().1 .1 {
+                self.viewport_origin.1 += 1;
             }
         }
     }
 
-    pub fn hint(&mut self) {
-        let mut random = rand::thread_rng();
-        let mut non_bomb_cells: Vec<(u8, (usize, usize))> = Vec::new();
-        for row in 0..self.size.0 {
-            for column in 0..self.size.1 {
-                if !self.cells[row][column].is_bomb && !self.cells[row][column].is_discovered {
-                    non_bomb_cells.push((
-                        self.cells[row][column].number_of_adjusted_bombs,
-                        (row, column),
-                    ));
-                }
+    pub fn print_shown_frame(&self) {
+        self.screen.print_shown_frame();
+    }
+
+    pub fn to_saved_game(&self) -> SavedGame {
+        save_game(&self.game, &self.theme.name, self.elapsed())
+    }
+
+    pub fn to_replay(&self) -> Replay {
+        Replay {
+            version: REPLAY_VERSION,
+            game_code: self.game.game_code.to_string(),
+            resumed_from: self.resumed_from.clone(),
+            moves: self.recorded_moves.clone(),
+            final_cells: final_cells(&self.game),
+        }
+    }
+
+    // every move goes through here, to be kept in the history and the replay
+    fn make_move(&mut self, action: Action, cell: Option<(usize, usize)>) {
+        if !perform(&mut self.game, &mut self.history, action, cell) {
+            return;
+        }
+        // undo and redo come here too
+        self.bomb_probabilities = Probabilities::Stale;
+        self.recorded_moves.push(Move {
+            action,
+            cell,
+            milliseconds: self.elapsed().as_millis() as u64,
+        });
+        self.need_to_draw = true;
+        self.footer = None;
+
+        if self.game.status == GameStatus::OnGoing {
+            // a losing reveal can be undone during its animation
+            self.game_completion_status = GameCompletionStatus::GameIsOnGoing;
+            self.game_end_animation_step = 0;
+            self.highlighted_cell = Vec::new();
+            self.delay_before_draw = None;
+            let started = self
+                .game
+                .cells
+                .iter()
+                .flatten()
+                .any(|cell| cell.is_discovered);
+            if self.clock_started_at.is_none() && started {
+                self.clock_started_at = Some(Instant::now());
             }
         }
-        if non_bomb_cells.len() > 0 {
-            non_bomb_cells.sort_by_key(|x: &(u8, (usize, usize))| x.0);
-            let min_number_of_adjusted_bombs = non_bomb_cells[0].0;
-            let mut last_index: usize = 0;
-            for (index, cell) in non_bomb_cells.iter().enumerate() {
-                if cell.0 == min_number_of_adjusted_bombs {
-                    last_index = index
-                } else {
-                    break;
-                }
+    }
+
+    // points at a cell the solver can prove from the visible board,
+    // and only reveals a cell when nothing can be proven
+    pub fn hint(&mut self) {
+        if self.game.status != GameStatus::OnGoing {
+            return;
+        }
+        match explain(&self.game) {
+            Some(explanation) => {
+                self.game.hints_used += 1;
+                self.highlighted_cell = vec![explanation.cell];
+                self.selected_cell = Some(explanation.cell);
+                self.keep_in_view();
+                self.footer = Some(explanation.describe(&self.game));
+                self.need_to_draw = true;
             }
-            let i = random.gen_range(0..=last_index);
-            self.discover_cell(non_bomb_cells[i].1);
+            None => self.make_move(Action::Hint, None),
         }
     }
 
+    pub fn undo(&mut self) {
+        self.make_move(Action::Undo, None);
+    }
+
+    pub fn redo(&mut self) {
+        self.make_move(Action::Redo, None);
+    }
+
+    // shows a game of a replay, with the cell of its last move selected
+    pub fn show_game(
+        &mut self,
+        game: Game,
+        selected_cell: Option<(usize, usize)>,
+        elapsed: Duration,
+    ) {
+        self.game = game;
+        self.bomb_probabilities = Probabilities::Stale;
+        self.selected_cell = selected_cell;
+        self.clock_started_at = None;
+        self.elapsed_before_clock_start = elapsed;
+        self.keep_in_view();
+        self.need_to_draw = true;
+    }
+
     pub fn mouse_hover(&mut self, mouse_row: usize, mouse_column: usize) {
         let index = self.convert_mouse_to_index(mouse_row, mouse_column);
         if let Some((row, column)) = index {
@@ -165,60 +522,124 @@ impl Board {
     }
 
     pub fn mouse_down(&mut self, mouse_row: usize, mouse_column: usize, left_key: bool) {
+        let other_key_held = if left_key {
+            self.mouse_buttons_held.1
+        } else {
+            self.mouse_buttons_held.0
+        };
+        if left_key {
+            self.mouse_buttons_held.0 = true;
+        } else {
+            self.mouse_buttons_held.1 = true;
+        }
         let index = self.convert_mouse_to_index(mouse_row, mouse_column);
-        if let Some((row, column)) = index {
+        if other_key_held {
+            // the click of the first button becomes a part of the chord
+            self.mouse_pressed_cell = None;
+            if let Some((row, column)) = index {
+                self.chord(row, column);
+            }
+        } else {
+            self.mouse_pressed_cell = index;
+        }
+    }
+
+    // a click acts when its button goes up, unless the other button went down meanwhile
+    pub fn mouse_up(&mut self, left_key: bool) {
+        if left_key {
+            self.mouse_buttons_held.0 = false;
+        } else {
+            self.mouse_buttons_held.1 = false;
+        }
+        if let Some((row, column)) = self.mouse_pressed_cell.take() {
             self.intract_with_cell(row, column, !left_key);
         }
     }
 
+    pub fn mouse_chord(&mut self, mouse_row: usize, mouse_column: usize) {
+        let index = self.convert_mouse_to_index(mouse_row, mouse_column);
+        if let Some((row, column)) = index {
+            self.chord(row, column);
+        }
+    }
+
+    pub fn chord(&mut self, row: usize, column: usize) {
+        let action = match self.chord_mode {
+            ChordMode::Classic => Action::ClassicChord,
+            ChordMode::Smart => Action::Chord,
+        };
+        self.make_move(action, Some((row, column)));
+    }
+
     pub fn intract_with_cell(&mut self, row: usize, column: usize, alternate_key: bool) {
-        if self.cells[row][column].is_discovered {
-            // fill flags for adjusted cells, if possible
-            self.discover_or_flag_adjusted_cells((row, column));
-        } else {
-            if !alternate_key {
-                // discover or undo flag
-                if self.cells[row][column].is_flagged {
-                    self.set_cell_flag((row, column), false);
-                } else {
-                    self.discover_cell((row, column));
-                }
+        let cell = &self.game.cells[row][column];
+        let action = if cell.is_discovered {
+            // a click on a number chords, unless the chords are classic
+            if self.chord_mode == ChordMode::Classic {
+                return;
+            }
+            Action::Chord
+        } else if !alternate_key {
+            // discover or undo flag
+            if cell.is_flagged {
+                Action::Unflag
             } else {
-                // flag cell
-                if !self.cells[row][column].is_flagged {
-                    self.set_cell_flag((row, column), true);
-                }
+                Action::Reveal
+            }
+        } else if self.game.game_code.max_bombs_per_cell > 1 {
+            // cycle through the number of flags a cell can hold
+            Action::ToggleFlag
+        } else {
+            // flag cell
+            Action::Flag
+        };
+        self.make_move(action, Some((row, column)));
+    }
+
+    // the next cell in a direction, the rows of a hex board are shifted so the up and down
+    // steps zigzag between the two cells above or below to stay in the same column on screen;
+    // a board that wraps around goes on at the other side
+    fn step(&self, (row, column): (usize, usize), dr: isize, dc: isize) -> Option<(usize, usize)> {
+        let (rows, columns) = self.game.size;
+        if self.game.game_code.wrap {
+            return Some((
+                (row + rows).saturating_add_signed(dr) % rows,
+                (column + columns).saturating_add_signed(dc) % columns,
+            ));
+        }
+        let mut column = column.checked_add_signed(dc)?;
+        if dr != 0 && self.is_hex() {
+            if row % 2 == 0 && column > 0 {
+                column -= 1;
+            } else if row % 2 == 1 && column + 1 < columns {
+                column += 1;
             }
         }
+        let row = row.checked_add_signed(dr)?;
+        if row >= rows || column >= columns {
+            return None;
+        }
+        Some((row, column))
     }
 
     // move at direction specified with dr and dc while skipping the blank cells
     pub fn move_selection(&mut self, dr: isize, dc: isize) {
-        let mut next = if let Some((r, c)) = self.selected_cell {
-            (r as isize, c as isize)
-        } else {
-            (0, 0)
-        };
-
-        loop {
-            next.0 = next.0 + dr;
-            next.1 = next.1 + dc;
+        let start = self.selected_cell.unwrap_or((0, 0));
+        let mut next = start;
 
-            // If out of bounds, stop
-            if next.0 < 0
-                || next.1 < 0
-                || next.0 >= self.size.0 as isize
-                || next.1 >= self.size.1 as isize
-            {
+        // If out of bounds, or around a board that wraps, stop
+        while let Some((row, column)) = self.step(next, dr, dc) {
+            if (row, column) == start {
                 break;
             }
-
-            let cell = &self.cells[next.0 as usize][next.1 as usize];
+            next = (row, column);
+            let cell = &self.game.cells[row][column];
 
             if cell.is_discovered && cell.number_of_adjusted_bombs == 0 {
                 continue;
             } else {
-                self.selected_cell = Some((next.0 as usize, next.1 as usize));
+                self.selected_cell = Some(next);
+                self.keep_in_view();
                 self.need_to_draw = true;
                 break;
             }
@@ -227,11 +648,7 @@ impl Board {
 
     pub fn flag_selected(&mut self) {
         if let Some((r, c)) = self.selected_cell {
-            if !self.cells[r][c].is_discovered {
-                let flag = !self.cells[r][c].is_flagged;
-                self.set_cell_flag((r, c), flag);
-                self.need_to_draw = true;
-            }
+            self.make_move(Action::ToggleFlag, Some((r, c)));
         }
     }
 
@@ -241,6 +658,12 @@ impl Board {
         }
     }
 
+    pub fn chord_selected(&mut self) {
+        if let Some((r, c)) = self.selected_cell {
+            self.chord(r, c);
+        }
+    }
+
     fn is_cell_highlighted(&self, cell: &(usize, usize)) -> bool {
         (self.selected_cell == Some(*cell)) || self.highlighted_cell.contains(cell)
     }
@@ -251,163 +674,266 @@ impl Board {
         } else {
             self.need_to_draw = false;
         }
-        // clear terminal
-        queue!(stdout, Clear(ClearType::All))?;
-        queue!(stdout, Clear(ClearType::Purge))?;
-        queue!(stdout, MoveTo(0, 0))?;
+        self.update_bomb_probabilities();
+        let frame = self.build_frame();
+        self.screen.render(&mut stdout, frame)
+    }
 
-        for row in 0..self.size.0 {
+    // the chances are only worked out when they are shown and the game has changed,
+    // not on every tick of the clock or move of the selection
+    fn update_bomb_probabilities(&mut self) {
+        if self.show_bomb_probabilities && self.bomb_probabilities == Probabilities::Stale {
+            self.bomb_probabilities = match bomb_probabilities(&self.game) {
+                Some(probabilities) => Probabilities::Ready(probabilities),
+                None => Probabilities::Unavailable,
+            };
+        }
+    }
+
+    fn build_frame(&self) -> Frame {
+        if let Some(overlay) = self.overlay {
+            let lines = match overlay {
+                Overlay::Help => self.help_lines(),
+                Overlay::Pause => vec![
+                    "Paused".to_owned(),
+                    String::new(),
+                    format!(
+                        "time: {}  mines left: {}  cells left: {}",
+                        format_elapsed(self.elapsed()),
+                        self.game.remaining_flags,
+                        self.game.undiscovered_safe_cells()
+                    ),
+                    String::new(),
+                    "Press any key to continue".to_owned(),
+                ],
+            };
+            // the help may not fit, the status and footer lines are free
+            return lines
+                .iter()
+                .take(self.available_size().0.saturating_add(2))
+                .map(|line| text_pieces(line, self.available_size().1))
+                .collect();
+        }
+        let mut frame: Frame = Vec::new();
+        let bomb_probabilities = match &self.bomb_probabilities {
+            Probabilities::Ready(probabilities) if self.show_bomb_probabilities => {
+                Some(probabilities)
+            }
+            _ => None,
+        };
+
+        let ((first_row, row_end), (first_column, column_end)) = self.viewport();
+        let last_row_shown = row_end == self.game.size.0;
+        let last_column_shown = column_end == self.game.size.1;
+        let wrap = self.game.game_code.wrap;
+
+        for row in first_row..row_end {
             // each row has two parts, one for border and one for the content
             // outer/inner border row
-            let mut line1 = String::new();
-            for column in 0..self.size.1 {
+            let mut line1 = Vec::new();
+            for column in first_column..column_end {
                 let selected = self.is_cell_highlighted(&(row, column));
                 let selected_on_left = column > 0 && self.is_cell_highlighted(&(row, column - 1));
                 let selected_on_top = row > 0 && self.is_cell_highlighted(&(row - 1, column));
                 let selected_on_top_left =
                     row > 0 && column > 0 && self.is_cell_highlighted(&(row - 1, column - 1));
-                if row == 0 && column == 0 {
-                    line1 += &self.theme.format_corner_top_left(selected);
+                if !self.has_column_border(column) {
+                    // the joints of the borders are only drawn above a column border
+                } else if row == 0 && column == 0 {
+                    line1.push(self.theme.format_corner_top_left(selected, wrap));
                 } else if row == 0 && column != 0 {
-                    line1 += &self.theme.format_edge_top(selected || selected_on_left);
+                    line1.push(
+                        self.theme
+                            .format_edge_top(selected || selected_on_left, wrap),
+                    );
                 } else if row != 0 && column == 0 {
-                    line1 += &self.theme.format_edge_left(selected || selected_on_top);
+                    line1.push(self.theme.format_edge_left(selected || selected_on_top));
                 } else if row != 0 && column != 0 {
-                    line1 += &self.theme.format_cross(
+                    line1.push(self.theme.format_cross(
                         selected || selected_on_left || selected_on_top || selected_on_top_left,
-                    );
+                    ));
                 }
                 // line (for space) + line (for content) + line (for space)
-                line1 += &self
-                    .theme
-                    .format_horizontal_border(selected || selected_on_top);
+                let horizontal_border = if row == 0 {
+                    self.theme
+                        .format_outer_horizontal_border(selected || selected_on_top, wrap)
+                } else {
+                    self.theme
+                        .format_horizontal_border(selected || selected_on_top)
+                };
+                for _ in 0..self.content_width() {
+                    line1.push(horizontal_border.clone());
+                }
                 if self.theme.cell_horizontal_padding_enabled {
-                    line1 += &self
-                        .theme
-                        .format_horizontal_border(selected || selected_on_top);
-                    line1 += &self
-                        .theme
-                        .format_horizontal_border(selected || selected_on_top);
+                    line1.push(horizontal_border.clone());
+                    line1.push(horizontal_border);
                 }
             }
             // outer border of the last column
-            let selected = self.is_cell_highlighted(&(row, &self.size.1 - 1));
-            let selected_on_top = row > 0 && self.is_cell_highlighted(&(row - 1, &self.size.1 - 1));
-            if row == 0 {
-                line1 += &self.theme.format_corner_top_right(selected);
-            } else {
-                line1 += &self.theme.format_edge_right(selected || selected_on_top);
+            if self.has_outer_border() && last_column_shown {
+                let selected = self.is_cell_highlighted(&(row, self.game.size.1 - 1));
+                let selected_on_top =
+                    row > 0 && self.is_cell_highlighted(&(row - 1, self.game.size.1 - 1));
+                if row == 0 {
+                    line1.push(self.theme.format_corner_top_right(selected, wrap));
+                } else {
+                    line1.push(self.theme.format_edge_right(selected || selected_on_top));
+                }
             }
-            if (row == 0 && self.theme.outer_border_enabled)
-                || (row != 0 && self.theme.inner_border_row_enabled)
-            {
-                println!("{}\r", line1);
+            if self.has_row_border(row) {
+                frame.push(line1);
             }
             // content row
-            let mut line2 = String::new();
-            for column in 0..self.size.1 {
+            let mut line2 = vec![" ".to_owned(); self.row_offset(row)];
+            for column in first_column..column_end {
                 let selected = self.is_cell_highlighted(&(row, column));
                 let selected_on_left = column > 0 && self.is_cell_highlighted(&(row, column - 1));
 
-                if (column == 0 && self.theme.outer_border_enabled)
-                    || (column != 0 && self.theme.inner_border_column_enabled)
-                {
-                    line2 += &self
-                        .theme
-                        .format_vertical_border(selected || selected_on_left);
+                if column == 0 && self.has_column_border(column) {
+                    line2.push(self.theme.format_outer_vertical_border(selected, wrap));
+                } else if self.has_column_border(column) {
+                    line2.push(
+                        self.theme
+                            .format_vertical_border(selected || selected_on_left),
+                    );
                 }
                 if self.theme.cell_horizontal_padding_enabled {
-                    line2 += &self.theme.cell_horizontal_padding;
+                    line2.push(self.theme.cell_horizontal_padding.clone());
+                }
+                let probability = bomb_probabilities
+                    .as_ref()
+                    .and_then(|probabilities| probabilities[row][column]);
+                let cell_content = match probability {
+                    Some(probability) if !selected => self
+                        .theme
+                        .format_bomb_probability(probability, self.content_width()),
+                    _ => self.theme.content_to_show(
+                        &self.game.cells[row][column],
+                        selected,
+                        self.content_width(),
+                    ),
+                };
+                for piece in cell_content {
+                    line2.push(self.theme.format_cell_content(&piece, selected));
                 }
-                let cell_content = self.cells[row][column].content_to_show(&self.theme, selected);
-                line2 += &self.theme.format_cell_content(&cell_content, selected);
                 if self.theme.cell_horizontal_padding_enabled {
-                    line2 += &self.theme.cell_horizontal_padding;
+                    line2.push(self.theme.cell_horizontal_padding.clone());
+                }
+                if self.is_hex() {
+                    line2.push(" ".to_owned());
                 }
             }
-            if self.theme.outer_border_enabled {
-                let sel = self.is_cell_highlighted(&(row, &self.size.1 - 1));
-                line2 += &self.theme.format_vertical_border(sel);
+            if self.has_outer_border() && last_column_shown {
+                let sel = self.is_cell_highlighted(&(row, self.game.size.1 - 1));
+                line2.push(self.theme.format_outer_vertical_border(sel, wrap));
             }
-            println!("{}\r", line2);
+            frame.push(line2);
         }
 
         // outer border of the last row
-        let mut line3 = String::new();
-        for column in 0..self.size.1 {
-            let selected = self.is_cell_highlighted(&(self.size.0 - 1, column));
+        let mut line3 = Vec::new();
+        for column in first_column..column_end {
+            let selected = self.is_cell_highlighted(&(self.game.size.0 - 1, column));
             let selected_on_left =
-                column > 0 && self.is_cell_highlighted(&(self.size.0 - 1, column - 1));
-            if column == 0 {
-                line3 += &self.theme.format_corner_bottom_left(selected);
+                column > 0 && self.is_cell_highlighted(&(self.game.size.0 - 1, column - 1));
+            if !self.has_column_border(column) {
+                // the joints of the borders are only drawn below a column border
+            } else if column == 0 {
+                line3.push(self.theme.format_corner_bottom_left(selected, wrap));
             } else {
-                line3 += &self.theme.format_edge_bottom(selected || selected_on_left);
+                line3.push(
+                    self.theme
+                        .format_edge_bottom(selected || selected_on_left, wrap),
+                );
             }
             // line (for space) + line (for content) + line (for space)
-            line3 += &self.theme.format_horizontal_border(selected);
+            let horizontal_border = self.theme.format_outer_horizontal_border(selected, wrap);
+            for _ in 0..self.content_width() {
+                line3.push(horizontal_border.clone());
+            }
             if self.theme.cell_horizontal_padding_enabled {
-                line3 += &self.theme.format_horizontal_border(selected);
-                line3 += &self.theme.format_horizontal_border(selected);
+                line3.push(horizontal_border.clone());
+                line3.push(horizontal_border);
             }
         }
-        let selected = self.is_cell_highlighted(&(self.size.0 - 1, self.size.1 - 1));
-        line3 += &self.theme.format_corner_bottom_right(selected);
-        if self.theme.outer_border_enabled {
-            println!("{}\r", line3);
+        if self.has_outer_border() && last_column_shown {
+            let selected = self.is_cell_highlighted(&(self.game.size.0 - 1, self.game.size.1 - 1));
+            line3.push(self.theme.format_corner_bottom_right(selected, wrap));
+        }
+        if self.has_outer_border() && last_row_shown {
+            frame.push(line3);
         }
 
-        println!("remaining flags: {}\r", self.remaining_flags);
-
-        Ok(())
+        let mut status = format!(
+            "time: {}  mines left: {}  cells left: {}  theme: {}",
+            format_elapsed(self.elapsed()),
+            self.game.remaining_flags,
+            self.game.undiscovered_safe_cells(),
+            self.theme.name
+        );
+        if self.show_bomb_probabilities {
+            let probability = match (&bomb_probabilities, self.selected_cell) {
+                (Some(probabilities), Some((row, column))) => probabilities[row][column],
+                _ => None,
+            };
+            status += &match (&bomb_probabilities, probability) {
+                (None, _) if self.game.game_code.max_bombs_per_cell > 1 => {
+                    "  bomb chance: only with one bomb in a cell".to_owned()
+                }
+                (None, _) => "  bomb chance: the flags do not fit the numbers".to_owned(),
+                (_, Some(probability)) => format!("  bomb chance: {:.0}%", probability * 100.0),
+                (_, None) => "  bomb chance: -".to_owned(),
+            };
+        }
+        if !last_row_shown || !last_column_shown || first_row > 0 || first_column > 0 {
+            status += &format!(
+                "  rows: {}-{}/{}  columns: {}-{}/{}",
+                first_row + 1,
+                row_end,
+                self.game.size.0,
+                first_column + 1,
+                column_end,
+                self.game.size.1
+            );
+        }
+        // a wrapped status line would scroll the board
+        frame.push(text_pieces(&status, self.available_size().1));
+        if let Some(footer) = &self.footer {
+            frame.push(text_pieces(footer, self.available_size().1));
+        }
+        frame
     }
 
     pub fn update(&mut self) {
         match self.game_completion_status {
-            GameCompletionStatus::GameIsOnGoing => {
-                // let mut bombs_without_flag = 0;
-                let mut non_bomb_cells_undicovered = 0;
-
-                for row in 0..self.size.0 {
-                    for column in 0..self.size.1 {
-                        if self.cells[row][column].is_bomb && self.cells[row][column].is_discovered
-                        {
-                            self.game_completion_status =
-                                GameCompletionStatus::LostAnimation(row, column);
-                            self.selected_cell = None;
-                            self.need_to_draw = true; // starts the game end animation
-                            self.delay_before_draw = Some(Duration::new(0, 200_000_000));
-                            return;
-                        }
-                        if self.cells[row][column].is_bomb && !self.cells[row][column].is_flagged {
-                            // bombs_without_flag += 1;
-                        }
-                        if !self.cells[row][column].is_bomb
-                            && !self.cells[row][column].is_discovered
-                        {
-                            non_bomb_cells_undicovered += 1;
-                        }
-                    }
+            GameCompletionStatus::GameIsOnGoing => match self.game.status {
+                GameStatus::Lost(row, column) => {
+                    self.stop_clock();
+                    self.game_completion_status = GameCompletionStatus::LostAnimation(row, column);
+                    self.selected_cell = None;
+                    self.need_to_draw = true; // starts the game end animation
+                    self.delay_before_draw = Some(Duration::new(0, 200_000_000));
                 }
-
-                // if bombs_without_flag == 0 || non_bomb_cells_undicovered == 0 {
-                if non_bomb_cells_undicovered == 0 {
+                GameStatus::Won => {
+                    self.stop_clock();
                     self.game_completion_status = GameCompletionStatus::WinAnimation;
                     self.selected_cell = None;
                     self.need_to_draw = true; // starts the game end animation
                     self.delay_before_draw = Some(Duration::new(0, 100_000_000));
                 }
-            }
+                GameStatus::OnGoing => (),
+            },
             GameCompletionStatus::WinAnimation => {
                 // last part of the animation
-                if self.game_end_animation_step > 0 && self.highlighted_cell.len() == 0 {
+                if self.game_end_animation_step > 0 && self.highlighted_cell.is_empty() {
                     self.game_completion_status = GameCompletionStatus::CompletedAsWin;
                     return;
                 }
 
                 // steps of the animation
                 self.highlighted_cell = Vec::new();
-                for row in 0..self.size.0 {
-                    for column in 0..self.size.1 {
+                for row in 0..self.game.size.0 {
+                    for column in 0..self.game.size.1 {
                         if row + column == self.game_end_animation_step {
                             self.highlighted_cell.push((row, column));
                         }
@@ -426,20 +952,16 @@ impl Board {
                 // steps of the animation
                 self.highlighted_cell = Vec::new();
                 self.highlighted_cell.push((bomb_row, bomb_column));
-                for _row in 0..self.size.0 {
-                    for _column in 0..self.size.1 {
+                for _row in 0..self.game.size.0 {
+                    for _column in 0..self.game.size.1 {
                         if (bomb_row.abs_diff(_row) == self.game_end_animation_step)
-                            && (
-                                bomb_column.abs_diff(_column) <= self.game_end_animation_step
-                            )
+                            && (bomb_column.abs_diff(_column) <= self.game_end_animation_step)
                         {
                             self.highlighted_cell.push((_row, _column));
                         }
 
                         if (bomb_column.abs_diff(_column) == self.game_end_animation_step)
-                            && (
-                                bomb_row.abs_diff(_row) <= self.game_end_animation_step
-                            )
+                            && (bomb_row.abs_diff(_row) <= self.game_end_animation_step)
                         {
                             self.highlighted_cell.push((_row, _column));
                         }
@@ -448,8 +970,8 @@ impl Board {
                 self.game_end_animation_step += 1;
                 self.need_to_draw = true;
             }
-            GameCompletionStatus::CompletedAsWin => return,
-            GameCompletionStatus::CompletedAsLost => return,
+            GameCompletionStatus::CompletedAsWin => (),
+            GameCompletionStatus::CompletedAsLost => (),
         }
     }
 
@@ -459,54 +981,46 @@ impl Board {
         mouse_row: usize,
         mouse_column: usize,
     ) -> Option<(usize, usize)> {
-        let column: Option<usize> = {
+        let ((first_row, row_end), (first_column, column_end)) = self.viewport();
+
+        let row: Option<usize> = {
             let mut result: Option<usize> = None;
 
             let mut start_index: usize;
             let mut end_index: usize = 0;
-            for test_column in 0..self.size.1 {
+            for test_row in first_row..row_end {
                 start_index = end_index;
-                if (test_column == 0 && self.theme.outer_border_enabled)
-                    || (test_column != 0 && self.theme.inner_border_column_enabled)
-                {
+                if self.has_row_border(test_row) {
                     start_index += 1;
-                    end_index += 1;
-                }
-                if self.theme.cell_horizontal_padding_enabled {
-                    end_index += 1;
-                }
-                end_index += 1;
-                if self.theme.cell_horizontal_padding_enabled {
-                    end_index += 1;
                 }
-                if start_index <= mouse_column && mouse_column < end_index {
-                    result = Some(test_column);
+                end_index += self.row_height(test_row);
+                if start_index <= mouse_row && mouse_row < end_index {
+                    result = Some(test_row);
                     break;
                 }
             }
+
             result
         };
 
-        let row: Option<usize> = {
+        // the cells of the odd rows of a hex board start further right
+        let offset = row.map_or(0, |row| self.row_offset(row));
+        let column: Option<usize> = {
             let mut result: Option<usize> = None;
 
             let mut start_index: usize;
             let mut end_index: usize = 0;
-            for test_row in 0..self.size.0 {
+            for test_column in first_column..column_end {
                 start_index = end_index;
-                if (test_row == 0 && self.theme.outer_border_enabled)
-                    || (test_row != 0 && self.theme.inner_border_row_enabled)
-                {
+                if self.has_column_border(test_column) {
                     start_index += 1;
-                    end_index += 1;
                 }
-                end_index += 1;
-                if start_index <= mouse_row && mouse_row < end_index {
-                    result = Some(test_row);
+                end_index += self.column_width(test_column);
+                if start_index + offset <= mouse_column && mouse_column < end_index + offset {
+                    result = Some(test_column);
                     break;
                 }
             }
-
             result
         };
 
@@ -518,108 +1032,92 @@ impl Board {
         None
     }
 
-    fn discover_cell(&mut self, (row, column): (usize, usize)) {
-        if !self.cells[row][column].is_discovered && !self.cells[row][column].is_flagged {
-            self.cells[row][column].is_discovered = true;
-            self.need_to_draw = true;
-            if self.cells[row][column].number_of_adjusted_bombs == 0 {
-                for index in self.get_adjusted_indices((row, column)) {
-                    self.discover_cell(index)
+    // the theme files that can not be loaded are skipped
+    pub fn change_theme(&mut self) {
+        let mut theme_name = rotate_theme_name(&self.theme.name);
+        while theme_name != self.theme.name {
+            match load_theme(&theme_name) {
+                Ok(theme) => {
+                    self.theme = theme;
+                    self.keep_in_view();
+                    self.need_to_draw = true;
+                    return;
                 }
+                Err(_) => theme_name = rotate_theme_name(&theme_name),
             }
         }
     }
 
-    fn set_cell_flag(&mut self, (row, column): (usize, usize), flag: bool) {
-        if self.cells[row][column].is_flagged != flag {
-            self.need_to_draw = true;
-            // need to change
-            if flag {
-                if self.remaining_flags > 0 {
-                    self.cells[row][column].is_flagged = flag;
-                    self.remaining_flags -= 1;
-                }
-            } else {
-                self.cells[row][column].is_flagged = flag;
-                self.remaining_flags += 1;
-            }
-        } else {
-            panic!("set_cell_flag: is_flagged == flag")
+    // seeing the chances counts as a hint
+    pub fn toggle_bomb_probabilities(&mut self) {
+        self.show_bomb_probabilities = !self.show_bomb_probabilities;
+        if self.show_bomb_probabilities && self.game.status == GameStatus::OnGoing {
+            self.game.hints_used += 1;
         }
+        self.need_to_draw = true;
     }
 
-    fn discover_or_flag_adjusted_cells(&mut self, (row, column): (usize, usize)) {
-        let adjusted_indices = &self.get_adjusted_indices((row, column));
-        let mut number_of_unknown_adjusted_cells = 0;
-        let mut number_of_flagged_adjusted_cells = 0;
-        for index in adjusted_indices {
-            if !self.cells[index.0][index.1].is_discovered
-                && !self.cells[index.0][index.1].is_flagged
-            {
-                number_of_unknown_adjusted_cells += 1;
-            }
-            if self.cells[index.0][index.1].is_flagged {
-                number_of_flagged_adjusted_cells += 1;
-            }
-        }
+    pub fn overlay(&self) -> Option<Overlay> {
+        self.overlay
+    }
 
-        if self.cells[row][column].number_of_adjusted_bombs == number_of_flagged_adjusted_cells {
-            for index in adjusted_indices {
-                self.discover_cell(*index);
-            }
-        } else if self.cells[row][column].number_of_adjusted_bombs
-            == number_of_flagged_adjusted_cells + number_of_unknown_adjusted_cells
-        {
-            for index in adjusted_indices {
-                if !self.cells[index.0][index.1].is_discovered
-                    && !self.cells[index.0][index.1].is_flagged
-                {
-                    self.set_cell_flag((index.0, index.1), true);
-                }
-            }
+    // the time behind an overlay is not played, so the clock waits for it
+    pub fn open_overlay(&mut self, overlay: Overlay) {
+        if self.overlay.is_none() && self.clock_started_at.is_some() {
+            self.stop_clock();
+            self.clock_stopped_by_overlay = true;
         }
+        self.overlay = Some(overlay);
+        self.need_to_draw = true;
     }
 
-    fn get_adjusted_indices(&mut self, (row, column): (usize, usize)) -> Vec<(usize, usize)> {
-        let mut result: Vec<(usize, usize)> = Vec::new();
+    pub fn close_overlay(&mut self) {
+        if self.clock_stopped_by_overlay {
+            self.clock_started_at = Some(Instant::now());
+            self.clock_stopped_by_overlay = false;
+        }
+        self.overlay = None;
+        self.need_to_draw = true;
+    }
 
-        // top
-        if row > 0 {
-            if column > 0 {
-                result.push((row - 1, column - 1));
-            }
-            result.push((row - 1, column));
-            if column + 1 < self.size.1 {
-                result.push((row - 1, column + 1));
-            }
+    // the rules of this game, then the keys
+    fn help_lines(&self) -> Vec<String> {
+        let game_code = &self.game.game_code;
+        let mut lines = vec![
+            "Open every cell without a bomb to win, opening a bomb loses the game.".to_owned(),
+            "A number counts the bombs around its cell, flag the cells you know hold a bomb."
+                .to_owned(),
+            "A chord on a number opens its other neighbours once it has as many flags.".to_owned(),
+        ];
+        if self.chord_mode == ChordMode::Smart {
+            lines.push(
+                "When they can only be bombs it flags them, and a click on a number chords."
+                    .to_owned(),
+            );
         }
-        // side
-        if column > 0 {
-            result.push((row, column - 1));
+        if game_code.no_guess {
+            lines.push(
+                "The board can be solved from the starting cell without guessing.".to_owned(),
+            );
+        } else if game_code.first_click_rule == FirstClickRule::Safe {
+            lines.push("The first click is never a bomb.".to_owned());
+        } else if game_code.first_click_rule == FirstClickRule::Zero {
+            lines.push("The first click opens an empty area.".to_owned());
         }
-        if column + 1 < self.size.1 {
-            result.push((row, column + 1));
+        if game_code.max_bombs_per_cell > 1 {
+            lines.push(format!(
+                "A cell holds up to {} bombs, and takes as many flags.",
+                game_code.max_bombs_per_cell
+            ));
         }
-        // bottom
-        if row + 1 < self.size.0 {
-            if column > 0 {
-                result.push((row + 1, column - 1));
-            }
-            result.push((row + 1, column));
-
-            if column + 1 < self.size.1 {
-                result.push((row + 1, column + 1));
-            }
-        }
-
-        result
-    }
-
-    pub fn change_theme(&mut self) {
-        if let Some(theme) = get_theme(&rotate_theme_name(&self.theme.name)) {
-            self.theme = theme;
-            self.need_to_draw = true;
+        if game_code.wrap {
+            lines.push("The edges join the opposite edges.".to_owned());
         }
+        lines.push(String::new());
+        lines.extend(self.key_bindings.format_help());
+        lines.push(String::new());
+        lines.push("Press any key to go back to the game".to_owned());
+        lines
     }
 
     pub fn change_theme_color(&mut self) {
@@ -630,13 +1128,21 @@ impl Board {
 
 #[cfg(test)]
 mod tests {
-    use crate::theme::border_theme;
+    use minesweeper::game::{
+        init_game_from_code, init_game_from_layout, init_game_from_state, FirstClickRule,
+        Neighbourhood,
+    };
+    use minesweeper::game_code::GameCode;
+
+    use unicode_width::UnicodeWidthStr;
+
+    use crate::theme::{border_theme, get_theme, parse_theme};
 
     use super::*;
 
     #[test]
     fn convet_mouse_to_index() {
-        let game_board = init_random_game((5, 10), 0.3, border_theme());
+        let game_board = init_board(init_game_from_layout((5, 10), &[]), border_theme());
 
         assert_eq!(game_board.convert_mouse_to_index(0, 0), None);
         assert_eq!(game_board.convert_mouse_to_index(0, 1), None);
@@ -654,4 +1160,309 @@ mod tests {
         assert_eq!(game_board.convert_mouse_to_index(1, 7), Some((0, 1)));
         assert_eq!(game_board.convert_mouse_to_index(1, 8), None);
     }
+
+    #[test]
+    fn viewport_follows_selection() {
+        let mut game_board = init_board(init_game_from_layout((30, 40), &[]), border_theme());
+        // 10 board lines fit 5 rows with their top borders, 20 columns fit 5 cells
+        game_board.resize(13, 20);
+        assert_eq!(game_board.viewport(), ((0, 5), (0, 5)));
+
+        game_board.selected_cell = Some((0, 4));
+        game_board.move_selection(0, 1);
+        assert_eq!(game_board.viewport(), ((0, 5), (1, 6)));
+        assert_eq!(game_board.convert_mouse_to_index(1, 17), Some((0, 5)));
+
+        game_board.pan(100, 100);
+        // the closing border takes a line and a column
+        assert_eq!(game_board.viewport(), ((26, 30), (36, 40)));
+        assert_eq!(game_board.convert_mouse_to_index(7, 1), Some((29, 36)));
+    }
+
+    #[test]
+    fn hex_rows_are_shifted() {
+        let game_code = GameCode {
+            size: (4, 4),
+            number_of_bombs: 0,
+            first_click_rule: FirstClickRule::Safe,
+            no_guess: false,
+            topology: Topology::Hex,
+            neighbourhood: Neighbourhood::Standard,
+            max_bombs_per_cell: 1,
+            wrap: false,
+            seed: 0,
+        };
+        let game = init_game_from_code(&game_code).unwrap();
+        let mut game_board = init_board(game, border_theme());
+
+        // no borders, a cell and its gap take 4 columns, the odd rows start 2 columns later
+        assert_eq!(game_board.convert_mouse_to_index(0, 0), Some((0, 0)));
+        assert_eq!(game_board.convert_mouse_to_index(0, 4), Some((0, 1)));
+        assert_eq!(game_board.convert_mouse_to_index(1, 1), None);
+        assert_eq!(game_board.convert_mouse_to_index(1, 2), Some((1, 0)));
+        assert_eq!(game_board.convert_mouse_to_index(1, 6), Some((1, 1)));
+        assert_eq!(game_board.convert_mouse_to_index(4, 0), None);
+
+        // up and down zigzag to stay in the same column on screen
+        game_board.selected_cell = Some((2, 2));
+        game_board.move_selection(-1, 0);
+        assert_eq!(game_board.selected_cell, Some((1, 1)));
+        game_board.move_selection(-1, 0);
+        assert_eq!(game_board.selected_cell, Some((0, 2)));
+        game_board.move_selection(1, 0);
+        assert_eq!(game_board.selected_cell, Some((1, 1)));
+        game_board.move_selection(0, 1);
+        assert_eq!(game_board.selected_cell, Some((1, 2)));
+    }
+
+    #[test]
+    fn selection_wraps_around() {
+        let game_code = GameCode {
+            size: (3, 4),
+            number_of_bombs: 0,
+            first_click_rule: FirstClickRule::Safe,
+            no_guess: false,
+            topology: Topology::Square,
+            neighbourhood: Neighbourhood::Standard,
+            max_bombs_per_cell: 1,
+            wrap: true,
+            seed: 0,
+        };
+        let game = init_game_from_code(&game_code).unwrap();
+        let mut game_board = init_board(game, get_theme("borderless").unwrap());
+
+        game_board.selected_cell = Some((0, 0));
+        game_board.move_selection(-1, 0);
+        assert_eq!(game_board.selected_cell, Some((2, 0)));
+        game_board.move_selection(0, -1);
+        assert_eq!(game_board.selected_cell, Some((2, 3)));
+
+        // the edges of a borderless theme are drawn too
+        assert_eq!(game_board.convert_mouse_to_index(0, 1), None);
+        assert_eq!(game_board.convert_mouse_to_index(1, 1), Some((0, 0)));
+    }
+
+    #[test]
+    fn wide_numbers_take_two_columns() {
+        let game_code = GameCode {
+            size: (3, 5),
+            number_of_bombs: 12,
+            first_click_rule: FirstClickRule::Off,
+            no_guess: false,
+            topology: Topology::Square,
+            neighbourhood: Neighbourhood::Radius(2),
+            max_bombs_per_cell: 1,
+            wrap: false,
+            seed: 0,
+        };
+        let bombs: Vec<(usize, usize)> = (0..3)
+            .flat_map(|row| (0..5).map(move |column| (row, column)))
+            .filter(|&cell| cell != (1, 2) && cell != (0, 0) && cell != (2, 4))
+            .collect();
+        let game = init_game_from_state(game_code, Some(&bombs), &[(1, 2)], &[]);
+        let mut theme = border_theme();
+        theme.number_colors = None;
+        let game_board = init_board(game, theme);
+
+        assert_eq!(game_board.content_width(), 2);
+        assert_eq!(game_board.column_width(0), 5);
+        assert_eq!(
+            game_board
+                .theme
+                .content_to_show(&game_board.game.cells[1][2], false, 2),
+            vec!["1", "2"]
+        );
+        assert_eq!(
+            game_board
+                .theme
+                .content_to_show(&game_board.game.cells[0][0], false, 2),
+            vec!["█", "█"]
+        );
+        assert_eq!(game_board.convert_mouse_to_index(1, 6), Some((0, 1)));
+        assert_eq!(game_board.convert_mouse_to_index(1, 9), Some((0, 1)));
+        assert_eq!(game_board.convert_mouse_to_index(1, 10), None);
+    }
+
+    #[test]
+    fn undo_losing_reveal() {
+        let mut game_board = init_board(
+            init_game_from_layout((3, 3), &[(0, 0), (2, 2)]),
+            border_theme(),
+        );
+        game_board.intract_with_cell(0, 2, false);
+        game_board.intract_with_cell(0, 0, false);
+        game_board.update();
+        assert_eq!(
+            game_board.game_completion_status,
+            GameCompletionStatus::LostAnimation(0, 0)
+        );
+
+        game_board.undo();
+        assert_eq!(
+            game_board.game_completion_status,
+            GameCompletionStatus::GameIsOnGoing
+        );
+        assert_eq!(game_board.game.status, GameStatus::OnGoing);
+        assert!(game_board.game.cells[0][2].is_discovered);
+        assert!(!game_board.game.cells[0][0].is_discovered);
+        assert_eq!(game_board.game.undos_used, 1);
+
+        game_board.redo();
+        assert_eq!(game_board.game.status, GameStatus::Lost(0, 0));
+        assert_eq!(game_board.game.undos_used, 1);
+    }
+
+    #[test]
+    fn classic_chord_needs_the_flags() {
+        let mut game_board = init_board(
+            init_game_from_layout((3, 3), &[(0, 0), (2, 2)]),
+            border_theme(),
+        );
+        game_board.chord_mode = ChordMode::Classic;
+        game_board.intract_with_cell(1, 1, false);
+        game_board.intract_with_cell(1, 1, false);
+        game_board.chord(1, 1);
+        assert!(!game_board.game.cells[0][1].is_discovered);
+
+        game_board.intract_with_cell(0, 0, true);
+        game_board.intract_with_cell(2, 2, true);
+        game_board.chord(1, 1);
+        assert_eq!(game_board.game.status, GameStatus::Won);
+        assert_eq!(
+            game_board.to_replay().moves.last().map(|last| last.action),
+            Some(Action::ClassicChord)
+        );
+    }
+
+    #[test]
+    fn both_mouse_buttons_chord_once() {
+        let mut game_board = init_board(
+            init_game_from_layout((3, 3), &[(0, 0), (2, 2)]),
+            border_theme(),
+        );
+        assert_eq!(game_board.convert_mouse_to_index(1, 9), Some((0, 2)));
+        game_board.mouse_down(1, 9, true);
+        game_board.mouse_down(1, 9, false);
+        game_board.mouse_up(false);
+        game_board.mouse_up(true);
+        assert!(!game_board.game.cells[0][2].is_discovered);
+        assert!(!game_board.game.cells[0][2].is_flagged);
+
+        assert_eq!(game_board.convert_mouse_to_index(3, 5), Some((1, 1)));
+        game_board.mouse_down(3, 5, true);
+        assert!(!game_board.game.cells[1][1].is_discovered);
+        game_board.mouse_up(true);
+        assert!(game_board.game.cells[1][1].is_discovered);
+        game_board.intract_with_cell(0, 0, true);
+        game_board.intract_with_cell(2, 2, true);
+        game_board.mouse_down(3, 5, true);
+        game_board.mouse_down(3, 5, false);
+        game_board.mouse_up(false);
+        game_board.mouse_up(true);
+        let chords = game_board
+            .to_replay()
+            .moves
+            .iter()
+            .filter(|last| last.action == Action::Chord)
+            .count();
+        assert_eq!(chords, 1);
+        assert_eq!(game_board.game.status, GameStatus::Won);
+    }
+
+    #[test]
+    fn pause_stops_the_clock() {
+        let mut game_board = init_board(
+            init_game_from_layout((3, 3), &[(0, 0), (2, 2)]),
+            border_theme(),
+        );
+        game_board.open_overlay(Overlay::Pause);
+        game_board.close_overlay();
+        assert_eq!(game_board.time_until_next_second(), None);
+
+        game_board.intract_with_cell(0, 2, false);
+        assert!(game_board.time_until_next_second().is_some());
+        game_board.open_overlay(Overlay::Pause);
+        game_board.open_overlay(Overlay::Help);
+        assert_eq!(game_board.time_until_next_second(), None);
+        let elapsed = game_board.elapsed();
+        assert_eq!(game_board.elapsed(), elapsed);
+
+        game_board.close_overlay();
+        assert_eq!(game_board.overlay(), None);
+        assert!(game_board.time_until_next_second().is_some());
+    }
+
+    #[test]
+    fn status_fits_a_narrow_terminal() {
+        let mut theme = border_theme();
+        theme.name = "mer_d'été_日本".to_owned();
+        let mut game_board = init_board(init_game_from_layout((3, 3), &[(0, 0)]), theme);
+        for columns in 40..70 {
+            game_board.resize(12, columns);
+            let frame = game_board.build_frame();
+            let status = frame.last().unwrap().concat();
+            assert!(status.width() <= columns);
+            assert!(status.starts_with("time: 00:00"));
+        }
+    }
+
+    #[test]
+    fn bomb_probabilities_are_kept_until_a_move() {
+        let mut game_board = init_board(
+            init_game_from_layout((3, 3), &[(0, 0), (2, 2)]),
+            border_theme(),
+        );
+        game_board.intract_with_cell(0, 2, false);
+        game_board.toggle_bomb_probabilities();
+        game_board.update_bomb_probabilities();
+        assert!(matches!(
+            game_board.bomb_probabilities,
+            Probabilities::Ready(_)
+        ));
+
+        game_board.move_selection(1, 0);
+        game_board.update_clock();
+        assert!(matches!(
+            game_board.bomb_probabilities,
+            Probabilities::Ready(_)
+        ));
+
+        game_board.intract_with_cell(0, 0, true);
+        assert_eq!(game_board.bomb_probabilities, Probabilities::Stale);
+        game_board.update_bomb_probabilities();
+        game_board.undo();
+        assert_eq!(game_board.bomb_probabilities, Probabilities::Stale);
+    }
+
+    #[test]
+    fn border_flags_keep_the_grid_aligned() {
+        for flags in 0..8 {
+            let content = format!(
+                "base = \"border\"\nouter_border_enabled = {}\ninner_border_row_enabled = {}\ninner_border_column_enabled = {}\n",
+                flags & 1 != 0,
+                flags & 2 != 0,
+                flags & 4 != 0
+            );
+            let theme = parse_theme("flags", &content).unwrap();
+            let game_board = init_board(init_game_from_layout((3, 4), &[(0, 0)]), theme);
+            let frame = game_board.build_frame();
+            let widths: Vec<usize> = frame[..frame.len() - 1]
+                .iter()
+                .map(|line| line.iter().filter(|piece| !piece.is_empty()).count())
+                .collect();
+            assert!(
+                widths.iter().all(|width| *width == widths[0]),
+                "{}: {:?}",
+                content,
+                widths
+            );
+        }
+    }
+
+    #[test]
+    fn format_elapsed_time() {
+        assert_eq!(format_elapsed(Duration::from_millis(59_900)), "00:59");
+        assert_eq!(format_elapsed(Duration::from_secs(61)), "01:01");
+        assert_eq!(format_elapsed(Duration::from_secs(3723)), "1:02:03");
+    }
 }
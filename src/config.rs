@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+// the defaults of the command-line options, every field is optional
+#[derive(Deserialize, Default, PartialEq, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub size: Option<String>,
+    pub bomb_percentage: Option<f32>,
+    pub mines: Option<usize>,
+    pub difficulty: Option<String>,
+    pub theme: Option<String>,
+    pub palette: Option<String>,
+    pub keys: Option<String>,
+    pub chord: Option<String>,
+    // the keys of some actions, like hint = ["i"], in place of the keys of the preset
+    pub key_bindings: Option<BTreeMap<String, Vec<String>>>,
+    pub first_click: Option<String>,
+    pub no_guess: Option<bool>,
+    pub topology: Option<String>,
+    pub neighbourhood: Option<String>,
+    pub max_mines_per_cell: Option<usize>,
+    pub wrap: Option<bool>,
+    pub seed: Option<u64>,
+}
+
+// the config file in the config directory, like ~/.config/minesweeper/config.toml
+pub fn config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|path| path.join("minesweeper").join("config.toml"))
+}
+
+pub fn parse_config(content: &str) -> Result<Config, String> {
+    let config: Config = toml::from_str(content).map_err(|e| e.message().to_owned())?;
+    check_config(&config)?;
+    Ok(config)
+}
+
+// the options that conflict on the command line can not be set together in the file either
+fn check_config(config: &Config) -> Result<(), String> {
+    let conflicts = [
+        (
+            "mines",
+            config.mines.is_some(),
+            "bomb_percentage",
+            config.bomb_percentage.is_some(),
+        ),
+        (
+            "difficulty",
+            config.difficulty.is_some(),
+            "size",
+            config.size.is_some(),
+        ),
+        (
+            "difficulty",
+            config.difficulty.is_some(),
+            "bomb_percentage",
+            config.bomb_percentage.is_some(),
+        ),
+        (
+            "difficulty",
+            config.difficulty.is_some(),
+            "mines",
+            config.mines.is_some(),
+        ),
+        (
+            "no_guess",
+            config.no_guess == Some(true),
+            "first_click",
+            config.first_click.is_some(),
+        ),
+    ];
+    for (first, first_set, second, second_set) in conflicts {
+        if first_set && second_set {
+            return Err(format!(
+                "`{}` and `{}` can not be set together, keep one of them",
+                first, second
+            ));
+        }
+    }
+    Ok(())
+}
+
+// a missing file is an empty config, unless it was asked for with --config
+pub fn read_config(path: &Path, must_exist: bool) -> Result<Config, String> {
+    if !must_exist && !path.exists() {
+        return Ok(Config::default());
+    }
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    parse_config(&content).map_err(|e| format!("{} is not a valid config: {}", path.display(), e))
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Source {
+    Default,
+    ConfigFile,
+    CommandLine,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Source::Default => "default",
+            Source::ConfigFile => "config file",
+            Source::CommandLine => "command line",
+        })
+    }
+}
+
+// the effective value of an option, formatted as toml, None when it is not set
+pub struct Setting {
+    pub name: &'static str,
+    pub value: Option<String>,
+    pub source: Source,
+}
+
+// the settings as a config file, with where each value came from
+pub fn format_settings(path: Option<&Path>, settings: &[Setting]) -> String {
+    let mut text = match path {
+        Some(path) if path.exists() => format!("# {}\n", path.display()),
+        Some(path) => format!("# {} (not found)\n", path.display()),
+        None => "# no config directory\n".to_owned(),
+    };
+    let lines: Vec<(String, String)> = settings
+        .iter()
+        .map(|setting| match &setting.value {
+            Some(value) => (
+                format!("{} = {}", setting.name, value),
+                setting.source.to_string(),
+            ),
+            None => (format!("# {}", setting.name), "not set".to_owned()),
+        })
+        .collect();
+    let width = lines.iter().map(|(line, _)| line.len()).max().unwrap_or(0);
+    for (line, source) in lines {
+        text.push_str(&format!("{:width$}  # {}\n", line, source, width = width));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_sets_some_options() {
+        let config = parse_config("size = \"16x16\"\nmines = 40\nwrap = true\n").unwrap();
+        assert_eq!(
+            config,
+            Config {
+                size: Some("16x16".to_owned()),
+                mines: Some(40),
+                wrap: Some(true),
+                ..Config::default()
+            }
+        );
+        assert!(parse_config("sise = \"16x16\"")
+            .unwrap_err()
+            .starts_with("unknown field `sise`"));
+        assert!(parse_config("mines = \"many\"").is_err());
+        assert_eq!(
+            parse_config("mines = 40\nbomb_percentage = 0.1\n"),
+            Err(
+                "`mines` and `bomb_percentage` can not be set together, keep one of them"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            parse_config("size = \"16x16\"\ndifficulty = \"expert\"\n"),
+            Err("`difficulty` and `size` can not be set together, keep one of them".to_owned())
+        );
+        assert!(parse_config("no_guess = false\nfirst_click = \"zero\"\n").is_ok());
+        assert_eq!(parse_config("seed = 42").unwrap().seed, Some(42));
+
+        let config = parse_config("keys = \"vim\"\n[key_bindings]\nhint = [\"i\"]\n").unwrap();
+        assert_eq!(config.keys, Some("vim".to_owned()));
+        assert_eq!(
+            config.key_bindings,
+            Some(BTreeMap::from([("hint".to_owned(), vec!["i".to_owned()])]))
+        );
+    }
+
+    #[test]
+    fn settings_show_their_source() {
+        let settings = [
+            Setting {
+                name: "size",
+                value: Some("\"16x16\"".to_owned()),
+                source: Source::ConfigFile,
+            },
+            Setting {
+                name: "mines",
+                value: None,
+                source: Source::Default,
+            },
+            Setting {
+                name: "wrap",
+                value: Some("true".to_owned()),
+                source: Source::CommandLine,
+            },
+        ];
+        assert_eq!(
+            format_settings(None, &settings),
+            "# no config directory\n\
+             size = \"16x16\"  # config file\n\
+             # mines         # not set\n\
+             wrap = true     # command line\n"
+        );
+    }
+}
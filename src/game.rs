@@ -0,0 +1,1213 @@
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::game_code::GameCode;
+use crate::solver::deduce;
+
+#[derive(Clone)]
+pub struct Cell {
+    pub is_bomb: bool,
+    // more than one only when the game allows several bombs in a cell
+    pub number_of_bombs: u8,
+    pub number_of_adjusted_bombs: usize,
+
+    pub is_flagged: bool,
+    pub number_of_flags: u8,
+    pub is_discovered: bool,
+}
+
+pub fn init_blank_cell() -> Cell {
+    Cell {
+        is_bomb: false,
+        number_of_bombs: 0,
+        number_of_adjusted_bombs: 0,
+        is_flagged: false,
+        number_of_flags: 0,
+        is_discovered: false,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameStatus {
+    OnGoing,
+    Won,
+    // the first discovered bomb
+    Lost(usize, usize),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FirstClickRule {
+    // bombs are placed before the first input and a starting cell is opened
+    Off,
+    // bombs are placed on the first reveal, never under the revealed cell
+    Safe,
+    // bombs are placed on the first reveal, away from the revealed cell and its neighbours
+    Zero,
+}
+
+pub fn get_first_click_rule(rule_name: &str) -> Option<FirstClickRule> {
+    match rule_name {
+        "off" => Some(FirstClickRule::Off),
+        "safe" => Some(FirstClickRule::Safe),
+        "zero" => Some(FirstClickRule::Zero),
+        _ => None,
+    }
+}
+
+// how the cells touch each other
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Topology {
+    // every cell touches the 8 cells around it
+    Square,
+    // the odd rows are shifted by half a cell, every cell touches 2 cells in its row
+    // and 2 in the rows above and below
+    Hex,
+}
+
+pub fn get_topology(topology_name: &str) -> Option<Topology> {
+    match topology_name {
+        "square" => Some(Topology::Square),
+        "hex" => Some(Topology::Hex),
+        _ => None,
+    }
+}
+
+// the number of bombs and flags in a cell is drawn with one digit
+pub const MAX_BOMBS_PER_CELL: usize = 9;
+
+// which cells of a square board count as the neighbours of a cell
+#[derive(Clone, PartialEq, Debug)]
+pub enum Neighbourhood {
+    // the 8 cells around
+    Standard,
+    // the 4 cells above, below, left and right
+    Cross,
+    // the 8 cells a knight jumps to in chess
+    Knight,
+    // the cells at most this many rows and columns away
+    Radius(usize),
+    // the steps from the cell as (rows, columns)
+    Offsets(Vec<(isize, isize)>),
+}
+
+// parses standard, cross, knight, radius2 or a list of offsets like 1,2;2,1;-1,-2
+// (or m1,m2 for the negative numbers, as they are printed in the game codes)
+pub fn get_neighbourhood(neighbourhood_name: &str) -> Option<Neighbourhood> {
+    match neighbourhood_name {
+        "standard" | "radius1" => Some(Neighbourhood::Standard),
+        "cross" => Some(Neighbourhood::Cross),
+        "knight" => Some(Neighbourhood::Knight),
+        _ => {
+            if let Some(radius) = neighbourhood_name.strip_prefix("radius") {
+                let radius = radius.parse::<usize>().ok()?;
+                return (radius > 0).then_some(Neighbourhood::Radius(radius));
+            }
+            let parse_step = |step: &str| match step.strip_prefix('m') {
+                Some(step) => step
+                    .parse::<isize>()
+                    .ok()
+                    .filter(|step| *step >= 0)
+                    .map(|step| -step),
+                None => step.parse::<isize>().ok(),
+            };
+            let mut offsets = Vec::new();
+            for offset in neighbourhood_name.split(';') {
+                let (row, column) = offset.split_once(',')?;
+                let offset = (parse_step(row.trim())?, parse_step(column.trim())?);
+                if offset != (0, 0) && !offsets.contains(&offset) {
+                    offsets.push(offset);
+                }
+            }
+            (!offsets.is_empty()).then_some(Neighbourhood::Offsets(offsets))
+        }
+    }
+}
+
+pub fn neighbourhood_name(neighbourhood: &Neighbourhood) -> String {
+    match neighbourhood {
+        Neighbourhood::Standard => "standard".to_owned(),
+        Neighbourhood::Cross => "cross".to_owned(),
+        Neighbourhood::Knight => "knight".to_owned(),
+        Neighbourhood::Radius(radius) => format!("radius{}", radius),
+        Neighbourhood::Offsets(offsets) => {
+            let format_step = |step: isize| {
+                if step < 0 {
+                    format!("m{}", step.unsigned_abs())
+                } else {
+                    step.to_string()
+                }
+            };
+            offsets
+                .iter()
+                .map(|&(row, column)| format!("{},{}", format_step(row), format_step(column)))
+                .collect::<Vec<String>>()
+                .join(";")
+        }
+    }
+}
+
+// the steps to the neighbours of a square board, from the rows above to the rows below
+fn neighbourhood_steps(neighbourhood: &Neighbourhood) -> Vec<(isize, isize)> {
+    match neighbourhood {
+        Neighbourhood::Standard => neighbourhood_steps(&Neighbourhood::Radius(1)),
+        Neighbourhood::Cross => vec![(-1, 0), (0, -1), (0, 1), (1, 0)],
+        Neighbourhood::Knight => vec![
+            (-2, -1),
+            (-2, 1),
+            (-1, -2),
+            (-1, 2),
+            (1, -2),
+            (1, 2),
+            (2, -1),
+            (2, 1),
+        ],
+        Neighbourhood::Radius(radius) => {
+            let radius = *radius as isize;
+            let mut steps = Vec::new();
+            for dr in -radius..=radius {
+                for dc in -radius..=radius {
+                    if (dr, dc) != (0, 0) {
+                        steps.push((dr, dc));
+                    }
+                }
+            }
+            steps
+        }
+        Neighbourhood::Offsets(offsets) => offsets.clone(),
+    }
+}
+
+// a step must be smaller than the larger side of the board, so the huge steps of a typo
+// can not overflow when the neighbours are found
+pub fn check_neighbourhood(
+    neighbourhood: &Neighbourhood,
+    (rows, columns): (usize, usize),
+) -> Result<(), String> {
+    let limit = rows.max(columns);
+    if let Neighbourhood::Radius(radius) = neighbourhood {
+        if *radius >= limit {
+            return Err(format!(
+                "the radius{} neighbourhood does not fit in a {}x{} board",
+                radius, columns, rows
+            ));
+        }
+    }
+    if let Neighbourhood::Offsets(offsets) = neighbourhood {
+        for &(row, column) in offsets {
+            if row.unsigned_abs() >= limit || column.unsigned_abs() >= limit {
+                return Err(format!(
+                    "the step {},{} of the neighbourhood does not fit in a {}x{} board",
+                    row, column, columns, rows
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+// the standard boards, as (size, number of bombs)
+pub fn get_difficulty(difficulty_name: &str) -> Option<((usize, usize), usize)> {
+    match difficulty_name {
+        "beginner" => Some(((9, 9), 10)),
+        "intermediate" => Some(((16, 16), 40)),
+        "expert" => Some(((16, 30), 99)),
+        _ => None,
+    }
+}
+
+// the options of a game code that can not be played together
+pub fn check_game_code(game_code: &GameCode) -> Result<(), String> {
+    if game_code.wrap && game_code.topology != Topology::Square {
+        return Err("only square boards can wrap around".to_owned());
+    }
+    if game_code.neighbourhood != Neighbourhood::Standard && game_code.topology != Topology::Square
+    {
+        return Err("only square boards can change the neighbourhood".to_owned());
+    }
+    check_neighbourhood(&game_code.neighbourhood, game_code.size)?;
+    if game_code.max_bombs_per_cell == 0 || game_code.max_bombs_per_cell > MAX_BOMBS_PER_CELL {
+        return Err(format!(
+            "a cell can hold 1 to {} bombs, but {} were asked",
+            MAX_BOMBS_PER_CELL, game_code.max_bombs_per_cell
+        ));
+    }
+    if game_code.max_bombs_per_cell > 1 && game_code.no_guess {
+        return Err("no-guess boards have at most one bomb in a cell".to_owned());
+    }
+    check_number_of_bombs(game_code)
+}
+
+// the bombs must leave room for the cells that the first click rule keeps safe
+pub fn check_number_of_bombs(game_code: &GameCode) -> Result<(), String> {
+    let (height, width) = game_code.size;
+    let game = init_blank_game(game_code.clone());
+    let safe_cells = if game_code.no_guess || game_code.first_click_rule == FirstClickRule::Zero {
+        let mut largest_area = 0;
+        for row in 0..height {
+            for column in 0..width {
+                largest_area = largest_area.max(game.get_adjusted_indices((row, column)).len() + 1);
+            }
+        }
+        largest_area
+    } else {
+        1
+    };
+
+    let room = (height * width).saturating_sub(safe_cells) * game_code.max_bombs_per_cell;
+    if game_code.number_of_bombs > room || safe_cells > height * width {
+        return Err(format!(
+            "a {}x{} board has room for at most {} bombs with a safe start of {} cells, but {} bombs were asked",
+            width,
+            height,
+            room,
+            safe_cells,
+            game_code.number_of_bombs
+        ));
+    }
+    Ok(())
+}
+
+// the cells touched by an action, so front-ends do not need to scan the whole board
+#[derive(Default, PartialEq, Debug)]
+pub struct Changes {
+    pub discovered_cells: Vec<(usize, usize)>,
+    pub flagged_cells: Vec<(usize, usize)>,
+    pub unflagged_cells: Vec<(usize, usize)>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.discovered_cells.is_empty()
+            && self.flagged_cells.is_empty()
+            && self.unflagged_cells.is_empty()
+    }
+
+    fn append(&mut self, mut other: Changes) {
+        self.discovered_cells.append(&mut other.discovered_cells);
+        self.flagged_cells.append(&mut other.flagged_cells);
+        self.unflagged_cells.append(&mut other.unflagged_cells);
+    }
+}
+
+// the rules of the game, without any terminal input or output
+#[derive(Clone)]
+pub struct Game {
+    pub size: (usize, usize),
+    pub cells: Vec<Vec<Cell>>,
+    pub number_of_bombs: usize,
+    pub remaining_flags: usize,
+    pub status: GameStatus,
+    pub game_code: GameCode,
+    pub hints_used: usize,
+    pub undos_used: usize,
+    first_click_rule: FirstClickRule,
+    bombs_placed: bool,
+    undiscovered_safe_cells: usize,
+    // the steps to the neighbours of a square board
+    neighbour_steps: Vec<(isize, isize)>,
+    // every random choice is drawn from here, so a seed always gives the same game
+    // (the generator of rand's StdRng, which can tell how far it has gone)
+    random: ChaCha12Rng,
+}
+
+fn init_blank_game(game_code: GameCode) -> Game {
+    let size = game_code.size;
+    let number_of_bombs = game_code.number_of_bombs;
+    Game {
+        size,
+        cells: vec![vec![init_blank_cell(); size.1]; size.0],
+        number_of_bombs,
+        remaining_flags: number_of_bombs,
+        status: GameStatus::OnGoing,
+        hints_used: 0,
+        undos_used: 0,
+        first_click_rule: game_code.first_click_rule,
+        bombs_placed: false,
+        undiscovered_safe_cells: (size.0 * size.1).saturating_sub(number_of_bombs),
+        neighbour_steps: neighbourhood_steps(&game_code.neighbourhood),
+        random: ChaCha12Rng::seed_from_u64(game_code.seed),
+        game_code,
+    }
+}
+
+pub fn init_random_game(
+    size: (usize, usize),
+    number_of_bombs: usize,
+    first_click_rule: FirstClickRule,
+    seed: u64,
+) -> Game {
+    generate_random_game(GameCode {
+        size,
+        number_of_bombs,
+        first_click_rule,
+        no_guess: false,
+        topology: Topology::Square,
+        wrap: false,
+        neighbourhood: Neighbourhood::Standard,
+        max_bombs_per_cell: 1,
+        seed,
+    })
+}
+
+fn generate_random_game(game_code: GameCode) -> Game {
+    let first_click_rule = game_code.first_click_rule;
+    let mut game = init_blank_game(game_code);
+
+    if first_click_rule == FirstClickRule::Off {
+        // generate bombs
+        game.place_bombs(&[]);
+
+        // make a starting point
+        game.discover_hint_cell();
+    }
+
+    game
+}
+
+pub fn init_game_from_layout(size: (usize, usize), bomb_cells: &[(usize, usize)]) -> Game {
+    let game_code = GameCode {
+        size,
+        number_of_bombs: bomb_cells.len(),
+        first_click_rule: FirstClickRule::Off,
+        no_guess: false,
+        topology: Topology::Square,
+        wrap: false,
+        neighbourhood: Neighbourhood::Standard,
+        max_bombs_per_cell: 1,
+        seed: 0,
+    };
+    let mut game = init_blank_game(game_code);
+    game.place_bombs_at(bomb_cells);
+    game
+}
+
+// rebuilds a game in the middle of play, bomb_cells is None when the bombs are not placed yet
+pub fn init_game_from_state(
+    game_code: GameCode,
+    bomb_cells: Option<&[(usize, usize)]>,
+    discovered_cells: &[(usize, usize)],
+    flagged_cells: &[(usize, usize)],
+) -> Game {
+    let mut game = init_blank_game(game_code);
+    if let Some(bomb_cells) = bomb_cells {
+        game.place_bombs_at(bomb_cells);
+    }
+    for &(row, column) in flagged_cells {
+        game.set_flag((row, column), true);
+    }
+    for &(row, column) in discovered_cells {
+        if !game.cells[row][column].is_discovered {
+            game.cells[row][column].is_discovered = true;
+            if game.cells[row][column].is_bomb {
+                if game.status == GameStatus::OnGoing {
+                    game.status = GameStatus::Lost(row, column);
+                }
+            } else {
+                game.undiscovered_safe_cells -= 1;
+            }
+        }
+    }
+    if game.bombs_placed && game.undiscovered_safe_cells == 0 && game.status == GameStatus::OnGoing
+    {
+        game.status = GameStatus::Won;
+    }
+    game
+}
+
+// the same game code always gives the same game
+pub fn init_game_from_code(game_code: &GameCode) -> Result<Game, String> {
+    check_game_code(game_code)?;
+    if game_code.no_guess {
+        generate_no_guess_game(GameCode {
+            first_click_rule: FirstClickRule::Zero,
+            ..game_code.clone()
+        })
+    } else {
+        Ok(generate_random_game(game_code.clone()))
+    }
+}
+
+const NO_GUESS_ATTEMPTS: usize = 1000;
+
+// generates random boards until one can be solved from the starting cell without guessing
+pub fn init_no_guess_game(
+    size: (usize, usize),
+    number_of_bombs: usize,
+    seed: u64,
+) -> Result<Game, String> {
+    generate_no_guess_game(GameCode {
+        size,
+        number_of_bombs,
+        first_click_rule: FirstClickRule::Zero,
+        no_guess: true,
+        topology: Topology::Square,
+        wrap: false,
+        neighbourhood: Neighbourhood::Standard,
+        max_bombs_per_cell: 1,
+        seed,
+    })
+}
+
+fn generate_no_guess_game(game_code: GameCode) -> Result<Game, String> {
+    let size = game_code.size;
+    let number_of_bombs = game_code.number_of_bombs;
+    let mut game = init_blank_game(game_code);
+
+    // the starting cell and its neighbours must be free of bombs
+    let mut starts: Vec<(usize, usize)> = Vec::new();
+    for row in 0..size.0 {
+        for column in 0..size.1 {
+            let start_area = game.get_adjusted_indices((row, column)).len() + 1;
+            if start_area + number_of_bombs <= size.0 * size.1 {
+                starts.push((row, column));
+            }
+        }
+    }
+    if starts.is_empty() {
+        return Err(format!(
+            "a {}x{} board with {} bombs has no room for a safe starting area, try fewer bombs",
+            size.1, size.0, number_of_bombs
+        ));
+    }
+
+    for _ in 0..NO_GUESS_ATTEMPTS {
+        let start = *starts.choose(&mut game.random).unwrap();
+        let mut safe_cells = game.get_adjusted_indices(start);
+        safe_cells.push(start);
+
+        game.cells = vec![vec![init_blank_cell(); size.1]; size.0];
+        game.number_of_bombs = number_of_bombs;
+        game.remaining_flags = number_of_bombs;
+        game.place_bombs(&safe_cells);
+
+        if game.is_solvable_from(start) {
+            // the solver played on the board, start it again from a clean state
+            for cell in game.cells.iter_mut().flatten() {
+                cell.is_discovered = false;
+                cell.is_flagged = false;
+                cell.number_of_flags = 0;
+            }
+            game.remaining_flags = game.number_of_bombs;
+            game.undiscovered_safe_cells = size.0 * size.1 - game.number_of_bombs;
+            game.status = GameStatus::OnGoing;
+            game.discover_cell(start);
+            return Ok(game);
+        }
+    }
+
+    Err(format!(
+        "could not generate a no-guess {}x{} board with {} bombs in {} attempts, try fewer bombs",
+        size.1, size.0, number_of_bombs, NO_GUESS_ATTEMPTS
+    ))
+}
+
+impl Game {
+    pub fn bombs_placed(&self) -> bool {
+        self.bombs_placed
+    }
+
+    pub fn undiscovered_safe_cells(&self) -> usize {
+        self.undiscovered_safe_cells
+    }
+
+    pub fn max_neighbours(&self) -> usize {
+        match self.game_code.topology {
+            Topology::Square => self.neighbour_steps.len(),
+            Topology::Hex => 6,
+        }
+    }
+
+    // the largest number a cell can show
+    pub fn max_number(&self) -> usize {
+        self.max_neighbours() * self.game_code.max_bombs_per_cell
+    }
+
+    fn place_bombs(&mut self, safe_cells: &[(usize, usize)]) {
+        let mut candidates: Vec<(usize, usize)> = Vec::new();
+        for row in 0..self.size.0 {
+            for column in 0..self.size.1 {
+                if !safe_cells.contains(&(row, column)) {
+                    candidates.push((row, column));
+                }
+            }
+        }
+        // a cell with room for several bombs is a candidate for each of them
+        let max_bombs_per_cell = self.game_code.max_bombs_per_cell;
+        let candidates: Vec<(usize, usize)> = candidates
+            .into_iter()
+            .flat_map(|index| std::iter::repeat_n(index, max_bombs_per_cell))
+            .collect();
+        // the board is too crowded to keep the whole area safe, only keep the first cell safe
+        if candidates.len() < self.number_of_bombs && safe_cells.len() > 1 {
+            return self.place_bombs(&safe_cells[..1]);
+        }
+
+        let bombs: Vec<(usize, usize)> = candidates
+            .choose_multiple(&mut self.random, self.number_of_bombs)
+            .cloned()
+            .collect();
+        self.place_bombs_at(&bombs);
+    }
+
+    // a cell listed more than once holds that many bombs
+    fn place_bombs_at(&mut self, bombs: &[(usize, usize)]) {
+        // flags placed before the first click are kept
+        self.remaining_flags =
+            (self.remaining_flags + bombs.len()).saturating_sub(self.number_of_bombs);
+        self.number_of_bombs = bombs.len();
+        self.undiscovered_safe_cells = self.size.0 * self.size.1;
+        for &(row, column) in bombs {
+            let cell = &mut self.cells[row][column];
+            if !cell.is_bomb {
+                self.undiscovered_safe_cells -= 1;
+            }
+            cell.is_bomb = true;
+            cell.number_of_bombs += 1;
+        }
+        self.fill_numbers();
+        self.bombs_placed = true;
+    }
+
+    // plays with the solver from the start cell, it discovers and flags cells on the board
+    fn is_solvable_from(&mut self, start: (usize, usize)) -> bool {
+        self.discover_cell(start);
+        loop {
+            let deduction = deduce(self);
+            if deduction.safe_cells.is_empty() && deduction.bomb_cells.is_empty() {
+                break;
+            }
+            for (row, column) in deduction.bomb_cells {
+                self.cells[row][column].is_flagged = true;
+                self.cells[row][column].number_of_flags = 1;
+            }
+            for index in deduction.safe_cells {
+                self.discover_cell(index);
+            }
+        }
+
+        self.undiscovered_safe_cells == 0
+    }
+
+    // place the bombs on the first reveal according to the first click rule
+    fn place_bombs_away_from(&mut self, (row, column): (usize, usize)) {
+        let mut safe_cells = vec![(row, column)];
+        if self.first_click_rule == FirstClickRule::Zero {
+            safe_cells.append(&mut self.get_adjusted_indices((row, column)));
+        }
+        self.place_bombs(&safe_cells);
+    }
+
+    fn fill_numbers(&mut self) {
+        for row in 0..self.size.0 {
+            for column in 0..self.size.1 {
+                for index in self.get_adjusted_indices((row, column)) {
+                    self.cells[row][column].number_of_adjusted_bombs +=
+                        self.cells[index.0][index.1].number_of_bombs as usize;
+                }
+            }
+        }
+    }
+
+    // how far the random choices have gone, a saved game goes on from there when it is loaded
+    pub fn random_position(&self) -> u128 {
+        self.random.get_word_pos()
+    }
+
+    pub fn set_random_position(&mut self, position: u128) {
+        self.random.set_word_pos(position);
+    }
+
+    // discovers a random non-bomb cell with the lowest number
+    pub fn hint(&mut self) -> Changes {
+        if self.status != GameStatus::OnGoing {
+            return Changes::default();
+        }
+        let changes = self.discover_hint_cell();
+        if !changes.is_empty() {
+            self.hints_used += 1;
+        }
+        changes
+    }
+
+    fn discover_hint_cell(&mut self) -> Changes {
+        if !self.bombs_placed {
+            // the hinted cell acts as the first click
+            let start = (
+                self.random.gen_range(0..self.size.0),
+                self.random.gen_range(0..self.size.1),
+            );
+            self.place_bombs_away_from(start);
+        }
+        let mut non_bomb_cells: Vec<(usize, (usize, usize))> = Vec::new();
+        for row in 0..self.size.0 {
+            for column in 0..self.size.1 {
+                if !self.cells[row][column].is_bomb && !self.cells[row][column].is_discovered {
+                    non_bomb_cells.push((
+                        self.cells[row][column].number_of_adjusted_bombs,
+                        (row, column),
+                    ));
+                }
+            }
+        }
+        if non_bomb_cells.is_empty() {
+            return Changes::default();
+        }
+        non_bomb_cells.sort_by_key(|x: &(usize, (usize, usize))| x.0);
+        let min_number_of_adjusted_bombs = non_bomb_cells[0].0;
+        let mut last_index: usize = 0;
+        for (index, cell) in non_bomb_cells.iter().enumerate() {
+            if cell.0 == min_number_of_adjusted_bombs {
+                last_index = index
+            } else {
+                break;
+            }
+        }
+        let i = self.random.gen_range(0..=last_index);
+        let (row, column) = non_bomb_cells[i].1;
+        // remove a wrong flag from the hinted cell
+        let mut changes = Changes::default();
+        if self.cells[row][column].is_flagged {
+            changes.append(self.set_flag((row, column), false));
+        }
+        changes.append(self.discover_cell((row, column)));
+        changes
+    }
+
+    // discovers an undiscovered and unflagged cell, and the empty area around it
+    pub fn reveal(&mut self, (row, column): (usize, usize)) -> Changes {
+        if self.status != GameStatus::OnGoing {
+            return Changes::default();
+        }
+        if !self.bombs_placed && !self.cells[row][column].is_flagged {
+            self.place_bombs_away_from((row, column));
+        }
+        self.discover_cell((row, column))
+    }
+
+    // adds one flag to the cell, or takes all of its flags away
+    pub fn set_flag(&mut self, (row, column): (usize, usize), flag: bool) -> Changes {
+        let number_of_flags = if flag {
+            self.cells[row][column].number_of_flags as usize + 1
+        } else {
+            0
+        };
+        self.set_number_of_flags((row, column), number_of_flags)
+    }
+
+    // adds one flag until the cell holds as many flags as it can hold bombs, then takes them all away
+    pub fn toggle_flag(&mut self, (row, column): (usize, usize)) -> Changes {
+        let number_of_flags = self.cells[row][column].number_of_flags as usize;
+        let flag = number_of_flags < self.game_code.max_bombs_per_cell && self.remaining_flags > 0;
+        self.set_flag((row, column), flag)
+    }
+
+    fn set_number_of_flags(
+        &mut self,
+        (row, column): (usize, usize),
+        number_of_flags: usize,
+    ) -> Changes {
+        let mut changes = Changes::default();
+        let current_flags = self.cells[row][column].number_of_flags as usize;
+        if self.status != GameStatus::OnGoing
+            || self.cells[row][column].is_discovered
+            || number_of_flags == current_flags
+            || number_of_flags > self.game_code.max_bombs_per_cell
+        {
+            return changes;
+        }
+        let number_of_flags = if number_of_flags > current_flags {
+            let added_flags = (number_of_flags - current_flags).min(self.remaining_flags);
+            if added_flags == 0 {
+                return changes;
+            }
+            self.remaining_flags -= added_flags;
+            changes.flagged_cells.push((row, column));
+            current_flags + added_flags
+        } else {
+            self.remaining_flags += current_flags - number_of_flags;
+            changes.unflagged_cells.push((row, column));
+            number_of_flags
+        };
+        self.cells[row][column].number_of_flags = number_of_flags as u8;
+        self.cells[row][column].is_flagged = number_of_flags > 0;
+        changes
+    }
+
+    // on a discovered number: discovers the neighbours when all of its bombs are flagged,
+    // or fills the neighbours with flags when they can only be full of bombs
+    pub fn chord(&mut self, cell: (usize, usize)) -> Changes {
+        self.chord_with(cell, true)
+    }
+
+    // on a discovered number: only discovers the neighbours when it has as many flags around it,
+    // a wrong flag leaves a bomb to be discovered
+    pub fn classic_chord(&mut self, cell: (usize, usize)) -> Changes {
+        self.chord_with(cell, false)
+    }
+
+    fn chord_with(&mut self, (row, column): (usize, usize), fill_flags: bool) -> Changes {
+        let mut changes = Changes::default();
+        if self.status != GameStatus::OnGoing || !self.cells[row][column].is_discovered {
+            return changes;
+        }
+        let max_bombs_per_cell = self.game_code.max_bombs_per_cell;
+        let adjusted_indices = &self.get_adjusted_indices((row, column));
+        // the bombs that the undiscovered neighbours can hold, and their flags
+        let mut room_for_bombs = 0;
+        let mut number_of_adjusted_flags = 0;
+        for index in adjusted_indices {
+            if !self.cells[index.0][index.1].is_discovered {
+                room_for_bombs += max_bombs_per_cell;
+                number_of_adjusted_flags += self.cells[index.0][index.1].number_of_flags as usize;
+            }
+        }
+
+        let number_of_adjusted_bombs = self.cells[row][column].number_of_adjusted_bombs;
+        if number_of_adjusted_bombs == number_of_adjusted_flags {
+            for index in adjusted_indices {
+                changes.append(self.discover_cell(*index));
+            }
+        } else if fill_flags && number_of_adjusted_bombs == room_for_bombs {
+            for index in adjusted_indices {
+                if !self.cells[index.0][index.1].is_discovered {
+                    changes.append(self.set_number_of_flags(*index, max_bombs_per_cell));
+                }
+            }
+        }
+        changes
+    }
+
+    fn discover_cell(&mut self, (row, column): (usize, usize)) -> Changes {
+        let mut changes = Changes::default();
+        let mut stack = vec![(row, column)];
+        while let Some((row, column)) = stack.pop() {
+            if self.cells[row][column].is_discovered || self.cells[row][column].is_flagged {
+                continue;
+            }
+            self.cells[row][column].is_discovered = true;
+            changes.discovered_cells.push((row, column));
+            if self.cells[row][column].is_bomb {
+                if self.status == GameStatus::OnGoing {
+                    self.status = GameStatus::Lost(row, column);
+                }
+                continue;
+            }
+            self.undiscovered_safe_cells -= 1;
+            if self.undiscovered_safe_cells == 0 && self.status == GameStatus::OnGoing {
+                self.status = GameStatus::Won;
+            }
+            if self.cells[row][column].number_of_adjusted_bombs == 0 {
+                stack.append(&mut self.get_adjusted_indices((row, column)));
+            }
+        }
+        changes
+    }
+
+    pub fn get_adjusted_indices(&self, (row, column): (usize, usize)) -> Vec<(usize, usize)> {
+        // the steps to the touched cells, from the row above to the row below
+        let steps: &[(isize, isize)] = match self.game_code.topology {
+            Topology::Square => &self.neighbour_steps,
+            Topology::Hex if row % 2 == 0 => &[(-1, -1), (-1, 0), (0, -1), (0, 1), (1, -1), (1, 0)],
+            Topology::Hex => &[(-1, 0), (-1, 1), (0, -1), (0, 1), (1, 0), (1, 1)],
+        };
+
+        let mut result: Vec<(usize, usize)> = Vec::new();
+        for &(dr, dc) in steps {
+            let index = if self.game_code.wrap {
+                // the steps past an edge come back on the other side
+                (
+                    (row as isize + dr).rem_euclid(self.size.0 as isize) as usize,
+                    (column as isize + dc).rem_euclid(self.size.1 as isize) as usize,
+                )
+            } else {
+                match (row.checked_add_signed(dr), column.checked_add_signed(dc)) {
+                    (Some(r), Some(c)) if r < self.size.0 && c < self.size.1 => (r, c),
+                    _ => continue,
+                }
+            };
+            // a narrow board that wraps reaches the same cell from both sides,
+            // or even the cell itself
+            if index != (row, column) && !result.contains(&index) {
+                result.push(index);
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_click_zero_opens_an_empty_area() {
+        let mut game = init_random_game((8, 8), 19, FirstClickRule::Zero, 0);
+        assert!(!game.bombs_placed);
+
+        game.reveal((4, 4));
+
+        assert!(game.bombs_placed);
+        assert!(!game.cells[4][4].is_bomb);
+        assert_eq!(game.cells[4][4].number_of_adjusted_bombs, 0);
+        for (row, column) in game.get_adjusted_indices((4, 4)) {
+            assert!(!game.cells[row][column].is_bomb);
+            assert!(game.cells[row][column].is_discovered);
+        }
+        let bombs = game.cells.iter().flatten().filter(|c| c.is_bomb).count();
+        assert_eq!(bombs, game.number_of_bombs);
+    }
+
+    #[test]
+    fn first_click_safe_keeps_flags() {
+        let mut game = init_random_game((3, 3), 8, FirstClickRule::Safe, 0);
+        game.set_flag((0, 0), true);
+        assert_eq!(game.remaining_flags, 7);
+
+        game.reveal((1, 1));
+
+        assert!(!game.cells[1][1].is_bomb);
+        assert!(game.cells[1][1].is_discovered);
+        assert_eq!(game.number_of_bombs, 8);
+        assert_eq!(game.remaining_flags, 7);
+        assert_eq!(game.status, GameStatus::Won);
+    }
+
+    #[test]
+    fn no_guess_game_is_solvable() {
+        let mut game = init_no_guess_game((8, 8), 10, 0).unwrap();
+        let start = game
+            .cells
+            .iter()
+            .flatten()
+            .position(|cell| cell.is_discovered)
+            .unwrap();
+
+        assert_eq!(game.number_of_bombs, 10);
+        assert!(game.is_solvable_from((start / 8, start % 8)));
+    }
+
+    #[test]
+    fn no_guess_game_needs_a_safe_start() {
+        assert!(init_no_guess_game((3, 3), 5, 0).is_ok());
+        assert!(init_no_guess_game((3, 3), 6, 0).is_err());
+    }
+
+    #[test]
+    fn same_seed_gives_same_game() {
+        let bombs =
+            |game: &Game| -> Vec<bool> { game.cells.iter().flatten().map(|c| c.is_bomb).collect() };
+        let mut first = init_random_game((8, 8), 19, FirstClickRule::Zero, 42);
+        let mut second = init_random_game((8, 8), 19, FirstClickRule::Zero, 42);
+        first.reveal((2, 3));
+        second.reveal((2, 3));
+        assert!(bombs(&first) == bombs(&second));
+
+        let first = init_no_guess_game((8, 8), 10, 7).unwrap();
+        let second = init_no_guess_game((8, 8), 10, 7).unwrap();
+        assert!(bombs(&first) == bombs(&second));
+    }
+
+    #[test]
+    fn actions_report_changes() {
+        // B . .
+        // . . .
+        // . . .
+        let mut game = init_game_from_layout((3, 3), &[(0, 0)]);
+
+        let changes = game.set_flag((0, 0), true);
+        assert_eq!(changes.flagged_cells, vec![(0, 0)]);
+        assert_eq!(game.remaining_flags, 0);
+
+        let changes = game.reveal((2, 2));
+        assert_eq!(changes.discovered_cells.len(), 8);
+        assert_eq!(game.status, GameStatus::Won);
+
+        assert!(game.reveal((0, 1)).is_empty());
+    }
+
+    #[test]
+    fn classic_chord_does_not_flag() {
+        // . . .
+        // . B B
+        // . B .
+        let mut game = init_game_from_layout((3, 3), &[(1, 1), (1, 2), (2, 1)]);
+        game.reveal((2, 2));
+
+        assert!(game.classic_chord((2, 2)).is_empty());
+        assert!(!game.chord((2, 2)).is_empty());
+        assert_eq!(game.remaining_flags, 0);
+    }
+
+    #[test]
+    fn chord_on_wrong_flag_loses() {
+        // B . .
+        // . . .
+        // . . .
+        let mut game = init_game_from_layout((3, 3), &[(0, 0)]);
+        game.reveal((1, 1));
+        game.set_flag((0, 1), true);
+
+        let changes = game.chord((1, 1));
+
+        assert!(changes.discovered_cells.contains(&(0, 0)));
+        assert_eq!(game.status, GameStatus::Lost(0, 0));
+    }
+
+    #[test]
+    fn number_of_bombs_leaves_a_safe_start() {
+        let mut game_code = GameCode {
+            size: (9, 9),
+            number_of_bombs: 80,
+            first_click_rule: FirstClickRule::Safe,
+            no_guess: false,
+            topology: Topology::Square,
+            wrap: false,
+            neighbourhood: Neighbourhood::Standard,
+            max_bombs_per_cell: 1,
+            seed: 0,
+        };
+        assert!(check_number_of_bombs(&game_code).is_ok());
+
+        game_code.first_click_rule = FirstClickRule::Zero;
+        assert_eq!(
+            check_number_of_bombs(&game_code),
+            Err("a 9x9 board has room for at most 72 bombs with a safe start of 9 cells, but 80 bombs were asked".to_owned())
+        );
+
+        game_code.number_of_bombs = 72;
+        assert!(check_number_of_bombs(&game_code).is_ok());
+    }
+
+    #[test]
+    fn hex_cells_touch_six_cells() {
+        let game_code = GameCode {
+            size: (5, 5),
+            number_of_bombs: 0,
+            first_click_rule: FirstClickRule::Safe,
+            no_guess: false,
+            topology: Topology::Hex,
+            wrap: false,
+            neighbourhood: Neighbourhood::Standard,
+            max_bombs_per_cell: 1,
+            seed: 0,
+        };
+        let game = init_game_from_code(&game_code).unwrap();
+        let mut neighbours = game.get_adjusted_indices((2, 2));
+        neighbours.sort();
+        assert_eq!(
+            neighbours,
+            vec![(1, 1), (1, 2), (2, 1), (2, 3), (3, 1), (3, 2)]
+        );
+        let mut neighbours = game.get_adjusted_indices((1, 2));
+        neighbours.sort();
+        assert_eq!(
+            neighbours,
+            vec![(0, 2), (0, 3), (1, 1), (1, 3), (2, 2), (2, 3)]
+        );
+        assert_eq!(game.get_adjusted_indices((0, 0)), vec![(0, 1), (1, 0)]);
+        assert_eq!(game.get_adjusted_indices((1, 4)).len(), 3);
+
+        // every cell touches its neighbours back
+        for row in 0..5 {
+            for column in 0..5 {
+                for neighbour in game.get_adjusted_indices((row, column)) {
+                    assert!(game
+                        .get_adjusted_indices(neighbour)
+                        .contains(&(row, column)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn wrapped_cells_touch_eight_cells() {
+        let game_code = GameCode {
+            size: (4, 5),
+            number_of_bombs: 2,
+            first_click_rule: FirstClickRule::Off,
+            no_guess: false,
+            topology: Topology::Square,
+            wrap: true,
+            neighbourhood: Neighbourhood::Standard,
+            max_bombs_per_cell: 1,
+            seed: 0,
+        };
+        let mut game = init_game_from_state(game_code.clone(), Some(&[(0, 0), (3, 4)]), &[], &[]);
+        let mut neighbours = game.get_adjusted_indices((0, 0));
+        neighbours.sort();
+        assert_eq!(
+            neighbours,
+            vec![
+                (0, 1),
+                (0, 4),
+                (1, 0),
+                (1, 1),
+                (1, 4),
+                (3, 0),
+                (3, 1),
+                (3, 4)
+            ]
+        );
+        // the bombs in opposite corners touch each other
+        assert_eq!(game.cells[0][0].number_of_adjusted_bombs, 1);
+        assert_eq!(game.cells[3][0].number_of_adjusted_bombs, 2);
+
+        // the empty area opens across the edges
+        game.reveal((2, 2));
+        assert!(game.cells[1][2].is_discovered);
+        assert_eq!(game.status, GameStatus::Won);
+
+        // a board of two columns has the same cell on both sides
+        let game_code = GameCode {
+            size: (3, 2),
+            ..game_code
+        };
+        let game = init_game_from_state(game_code, Some(&[]), &[], &[]);
+        assert_eq!(game.get_adjusted_indices((0, 0)).len(), 5);
+    }
+
+    #[test]
+    fn multi_mine_cells_count_every_bomb() {
+        let game_code = GameCode {
+            size: (3, 3),
+            number_of_bombs: 3,
+            first_click_rule: FirstClickRule::Off,
+            no_guess: false,
+            topology: Topology::Square,
+            neighbourhood: Neighbourhood::Standard,
+            max_bombs_per_cell: 2,
+            wrap: false,
+            seed: 0,
+        };
+        // 2 . .
+        // . . .
+        // . . 1
+        let bombs = [(0, 0), (0, 0), (2, 2)];
+        let mut game = init_game_from_state(game_code.clone(), Some(&bombs), &[], &[]);
+        assert_eq!(game.cells[0][0].number_of_bombs, 2);
+        assert_eq!(game.cells[1][1].number_of_adjusted_bombs, 3);
+        assert_eq!(game.cells[0][1].number_of_adjusted_bombs, 2);
+        assert_eq!(game.undiscovered_safe_cells(), 7);
+        assert_eq!(game.max_number(), 16);
+
+        // the flags cycle up to the bombs a cell can hold
+        game.toggle_flag((0, 0));
+        game.toggle_flag((0, 0));
+        assert_eq!(game.cells[0][0].number_of_flags, 2);
+        assert_eq!(game.remaining_flags, 1);
+        let changes = game.toggle_flag((0, 0));
+        assert_eq!(changes.unflagged_cells, vec![(0, 0)]);
+        assert_eq!(game.cells[0][0].number_of_flags, 0);
+        assert_eq!(game.remaining_flags, 3);
+
+        // the 2 has one unknown neighbour left, which is full of bombs
+        for index in [(0, 1), (0, 2), (1, 0), (1, 1), (1, 2)] {
+            game.reveal(index);
+        }
+        let changes = game.chord((0, 1));
+        assert_eq!(changes.flagged_cells, vec![(0, 0)]);
+        assert_eq!(game.cells[0][0].number_of_flags, 2);
+
+        game.set_flag((2, 2), true);
+        game.chord((1, 1));
+        assert_eq!(game.status, GameStatus::Won);
+
+        let game_code = GameCode {
+            max_bombs_per_cell: 5,
+            ..game_code
+        };
+        assert_eq!(check_game_code(&game_code), Ok(()));
+        for max_bombs_per_cell in 1..=MAX_BOMBS_PER_CELL {
+            let game_code = GameCode {
+                size: (8, 12),
+                number_of_bombs: 10,
+                max_bombs_per_cell,
+                ..game_code.clone()
+            };
+            assert_eq!(check_game_code(&game_code), Ok(()));
+        }
+        assert_eq!(
+            check_game_code(&GameCode {
+                max_bombs_per_cell: MAX_BOMBS_PER_CELL + 1,
+                ..game_code.clone()
+            }),
+            Err("a cell can hold 1 to 9 bombs, but 10 were asked".to_owned())
+        );
+        let game_code = GameCode {
+            size: (2, 2),
+            number_of_bombs: 7,
+            max_bombs_per_cell: 2,
+            first_click_rule: FirstClickRule::Safe,
+            ..game_code
+        };
+        assert!(check_game_code(&game_code).is_err());
+        let mut game = init_game_from_code(&GameCode {
+            number_of_bombs: 6,
+            ..game_code
+        })
+        .unwrap();
+        game.reveal((1, 1));
+        assert!(!game.cells[1][1].is_bomb);
+        assert_eq!(game.cells[1][1].number_of_adjusted_bombs, 6);
+    }
+
+    #[test]
+    fn neighbourhoods_change_the_numbers() {
+        assert_eq!(get_neighbourhood("radius2"), Some(Neighbourhood::Radius(2)));
+        assert_eq!(get_neighbourhood("radius1"), Some(Neighbourhood::Standard));
+        assert_eq!(
+            get_neighbourhood("1,2;-2,m1;1,2"),
+            Some(Neighbourhood::Offsets(vec![(1, 2), (-2, -1)]))
+        );
+        assert_eq!(get_neighbourhood("radius0"), None);
+        assert_eq!(get_neighbourhood("1;2"), None);
+        assert_eq!(get_neighbourhood("0,0"), None);
+        assert_eq!(get_neighbourhood("m-9223372036854775808,1"), None);
+
+        let game_code = GameCode {
+            size: (5, 5),
+            number_of_bombs: 3,
+            first_click_rule: FirstClickRule::Off,
+            no_guess: false,
+            topology: Topology::Square,
+            neighbourhood: Neighbourhood::Knight,
+            max_bombs_per_cell: 1,
+            wrap: false,
+            seed: 0,
+        };
+        let bombs = [(0, 1), (1, 1), (4, 3)];
+        let mut game = init_game_from_state(game_code.clone(), Some(&bombs), &[], &[]);
+        assert_eq!(game.max_neighbours(), 8);
+        // (0, 1) and (4, 3) are a knight move away, (1, 1) is next to it
+        assert_eq!(game.cells[2][2].number_of_adjusted_bombs, 2);
+        assert_eq!(game.cells[0][0].number_of_adjusted_bombs, 0);
+        // the empty area opens through the knight moves
+        game.reveal((0, 0));
+        assert!(game.cells[1][2].is_discovered);
+        assert!(game.cells[2][1].is_discovered);
+
+        // the numbers of a wide neighbourhood go past 35
+        let game_code = GameCode {
+            size: (9, 9),
+            number_of_bombs: 60,
+            neighbourhood: Neighbourhood::Radius(4),
+            ..game_code
+        };
+        assert_eq!(check_game_code(&game_code), Ok(()));
+        let game = init_game_from_code(&game_code).unwrap();
+        assert_eq!(game.max_number(), 80);
+        assert_eq!(
+            game.cells[4][4].number_of_adjusted_bombs,
+            60 - game.cells[4][4].number_of_bombs as usize
+        );
+        assert_eq!(
+            check_game_code(&GameCode {
+                neighbourhood: Neighbourhood::Radius(9),
+                ..game_code
+            }),
+            Err("the radius9 neighbourhood does not fit in a 9x9 board".to_owned())
+        );
+    }
+}
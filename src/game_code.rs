@@ -0,0 +1,262 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::game::{
+    check_neighbourhood, get_first_click_rule, get_neighbourhood, get_topology, neighbourhood_name,
+    FirstClickRule, Neighbourhood, Topology,
+};
+
+// everything needed to generate the same board again, printed like 12x8-19-off-2a9f03c4d1e5b7a8,
+// with the other options before the seed, like 12x8-19-off-hex-2a9f03c4d1e5b7a8 for a hex board
+// or 12x8-19-off-knight-wrap-2a9f03c4d1e5b7a8 for a board with knight move neighbours that wraps around,
+// and 12x8-19-off-multi3-2a9f03c4d1e5b7a8 for up to 3 bombs in a cell
+#[derive(Clone, PartialEq, Debug)]
+pub struct GameCode {
+    pub size: (usize, usize),
+    pub number_of_bombs: usize,
+    pub first_click_rule: FirstClickRule,
+    pub no_guess: bool,
+    pub topology: Topology,
+    pub neighbourhood: Neighbourhood,
+    // the numbers count the bombs, and a cell can hold up to this many of them
+    pub max_bombs_per_cell: usize,
+    // the edges touch the opposite edges
+    pub wrap: bool,
+    pub seed: u64,
+}
+
+pub fn topology_name(topology: Topology) -> &'static str {
+    match topology {
+        Topology::Square => "square",
+        Topology::Hex => "hex",
+    }
+}
+
+fn first_click_rule_name(first_click_rule: FirstClickRule) -> &'static str {
+    match first_click_rule {
+        FirstClickRule::Off => "off",
+        FirstClickRule::Safe => "safe",
+        FirstClickRule::Zero => "zero",
+    }
+}
+
+impl GameCode {
+    // the options that change the rules, like hex-knight-multi3-wrap, empty for a standard board
+    pub fn variant(&self) -> String {
+        let mut options: Vec<String> = Vec::new();
+        if self.topology != Topology::Square {
+            options.push(topology_name(self.topology).to_owned());
+        }
+        if self.neighbourhood != Neighbourhood::Standard {
+            options.push(neighbourhood_name(&self.neighbourhood));
+        }
+        if self.max_bombs_per_cell > 1 {
+            options.push(format!("multi{}", self.max_bombs_per_cell));
+        }
+        if self.wrap {
+            options.push("wrap".to_owned());
+        }
+        options.join("-")
+    }
+}
+
+impl fmt::Display for GameCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mode = if self.no_guess {
+            "noguess"
+        } else {
+            first_click_rule_name(self.first_click_rule)
+        };
+        write!(
+            f,
+            "{}x{}-{}-{}-",
+            self.size.1, self.size.0, self.number_of_bombs, mode
+        )?;
+        let variant = self.variant();
+        if !variant.is_empty() {
+            write!(f, "{}-", variant)?;
+        }
+        write!(f, "{:x}", self.seed)
+    }
+}
+
+impl FromStr for GameCode {
+    type Err = String;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        let wrong_code = || {
+            format!(
+                "wrong game code {}, enter it like 12x8-19-off-2a9f03c4",
+                code
+            )
+        };
+
+        let parts = code.trim().split('-').collect::<Vec<&str>>();
+        if parts.len() < 4 {
+            return Err(wrong_code());
+        }
+        let mut topology = Topology::Square;
+        let mut neighbourhood = Neighbourhood::Standard;
+        let mut max_bombs_per_cell = 1;
+        let mut wrap = false;
+        // the options are only read as they are printed: once each, in the order of variant
+        // and without the standard ones, so a game has one code
+        let mut last_kind = None;
+        for option in &parts[3..parts.len() - 1] {
+            let kind = if *option == "wrap" {
+                wrap = true;
+                3
+            } else if let Some(max) = option.strip_prefix("multi") {
+                max_bombs_per_cell = max.parse::<usize>().map_err(|_| wrong_code())?;
+                if max_bombs_per_cell < 2 || format!("multi{}", max_bombs_per_cell) != *option {
+                    return Err(wrong_code());
+                }
+                2
+            } else if let Some(option_topology) = get_topology(option) {
+                if option_topology == Topology::Square {
+                    return Err(wrong_code());
+                }
+                topology = option_topology;
+                0
+            } else {
+                neighbourhood = get_neighbourhood(option).ok_or_else(wrong_code)?;
+                if neighbourhood == Neighbourhood::Standard
+                    || neighbourhood_name(&neighbourhood) != *option
+                {
+                    return Err(wrong_code());
+                }
+                1
+            };
+            if last_kind.is_some_and(|last_kind| kind <= last_kind) {
+                return Err(wrong_code());
+            }
+            last_kind = Some(kind);
+        }
+        let size = parts[0].split('x').collect::<Vec<&str>>();
+        if size.len() != 2 {
+            return Err(wrong_code());
+        }
+        let width = size[0].parse::<usize>().map_err(|_| wrong_code())?;
+        let height = size[1].parse::<usize>().map_err(|_| wrong_code())?;
+        if width == 0 || height == 0 {
+            return Err(wrong_code());
+        }
+        let number_of_bombs = parts[1].parse::<usize>().map_err(|_| wrong_code())?;
+        let (first_click_rule, no_guess) = if parts[2] == "noguess" {
+            (FirstClickRule::Zero, true)
+        } else {
+            (
+                get_first_click_rule(parts[2]).ok_or_else(wrong_code)?,
+                false,
+            )
+        };
+        let seed = u64::from_str_radix(parts[parts.len() - 1], 16).map_err(|_| wrong_code())?;
+        check_neighbourhood(&neighbourhood, (height, width))?;
+
+        Ok(GameCode {
+            size: (height, width),
+            number_of_bombs,
+            first_click_rule,
+            no_guess,
+            topology,
+            neighbourhood,
+            max_bombs_per_cell,
+            wrap,
+            seed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn game_code_round_trip() {
+        let game_code = GameCode {
+            size: (8, 12),
+            number_of_bombs: 19,
+            first_click_rule: FirstClickRule::Safe,
+            no_guess: false,
+            topology: Topology::Square,
+            neighbourhood: Neighbourhood::Standard,
+            max_bombs_per_cell: 1,
+            wrap: false,
+            seed: 0x2a9f03c4,
+        };
+        assert_eq!(game_code.to_string(), "12x8-19-safe-2a9f03c4");
+        assert_eq!("12x8-19-safe-2a9f03c4".parse(), Ok(game_code.clone()));
+
+        let game_code = GameCode {
+            topology: Topology::Hex,
+            ..game_code
+        };
+        assert_eq!(game_code.to_string(), "12x8-19-safe-hex-2a9f03c4");
+        assert_eq!("12x8-19-safe-hex-2a9f03c4".parse(), Ok(game_code.clone()));
+
+        let game_code = GameCode {
+            topology: Topology::Square,
+            wrap: true,
+            ..game_code
+        };
+        assert_eq!(game_code.to_string(), "12x8-19-safe-wrap-2a9f03c4");
+        assert_eq!("12x8-19-safe-wrap-2a9f03c4".parse(), Ok(game_code.clone()));
+
+        let game_code = GameCode {
+            neighbourhood: Neighbourhood::Offsets(vec![(1, 2), (-2, -1)]),
+            ..game_code
+        };
+        assert_eq!(
+            game_code.to_string(),
+            "12x8-19-safe-1,2;m2,m1-wrap-2a9f03c4"
+        );
+        assert_eq!(
+            "12x8-19-safe-1,2;m2,m1-wrap-2a9f03c4".parse(),
+            Ok(game_code.clone())
+        );
+
+        let game_code = GameCode {
+            neighbourhood: Neighbourhood::Standard,
+            max_bombs_per_cell: 3,
+            ..game_code
+        };
+        assert_eq!(game_code.to_string(), "12x8-19-safe-multi3-wrap-2a9f03c4");
+        assert_eq!("12x8-19-safe-multi3-wrap-2a9f03c4".parse(), Ok(game_code));
+
+        let game_code: GameCode = "30x16-99-noguess-ffffffffffffffff".parse().unwrap();
+        assert!(game_code.no_guess);
+        assert_eq!(game_code.seed, u64::MAX);
+
+        assert!("12x8-19-safe".parse::<GameCode>().is_err());
+        assert!("12x0-19-off-1".parse::<GameCode>().is_err());
+        assert!("12x8-19-unsafe-1".parse::<GameCode>().is_err());
+        assert!("12x8-19-safe-round-1".parse::<GameCode>().is_err());
+        assert!("12x8-19-safe-wrap-wrap-1".parse::<GameCode>().is_err());
+        assert!("12x8-19-safe-multi1-1".parse::<GameCode>().is_err());
+        for code in [
+            "12x8-19-safe-hex-square-1",
+            "12x8-19-safe-hex-hex-1",
+            "12x8-19-safe-square-1",
+            "12x8-19-safe-standard-1",
+            "12x8-19-safe-radius1-1",
+            "12x8-19-safe-knight-cross-1",
+            "12x8-19-safe-multi2-multi3-1",
+            "12x8-19-safe-multi03-1",
+            "12x8-19-safe-wrap-knight-1",
+            "12x8-19-safe-1,2;1,2-1",
+        ] {
+            assert!(code.parse::<GameCode>().is_err(), "{}", code);
+        }
+        assert!("12x8-19-safe-knight-multi2-wrap-1"
+            .parse::<GameCode>()
+            .is_ok());
+        assert!("12x8-19-safe-11,m11-wrap-1".parse::<GameCode>().is_ok());
+        assert_eq!(
+            "12x8-19-safe-12,1-wrap-1".parse::<GameCode>(),
+            Err("the step 12,1 of the neighbourhood does not fit in a 12x8 board".to_owned())
+        );
+        assert!("12x8-19-safe-m9223372036854775807,1-1"
+            .parse::<GameCode>()
+            .is_err());
+    }
+}
@@ -0,0 +1,49 @@
+use crate::game::Game;
+
+// the game before each move, and the undone moves
+#[derive(Default)]
+pub struct History {
+    undo_games: Vec<Game>,
+    redo_games: Vec<Game>,
+}
+
+impl History {
+    // a new move can not be redone over
+    pub fn record(&mut self, game_before: Game) {
+        self.undo_games.push(game_before);
+        self.redo_games.clear();
+    }
+
+    pub fn undo(&mut self, game: &mut Game) -> bool {
+        match self.undo_games.pop() {
+            Some(previous_game) => {
+                let undos_used = game.undos_used + 1;
+                let undone_game = switch_game(game, previous_game, undos_used);
+                self.redo_games.push(undone_game);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self, game: &mut Game) -> bool {
+        match self.redo_games.pop() {
+            Some(next_game) => {
+                let undos_used = game.undos_used;
+                let redone_game = switch_game(game, next_game, undos_used);
+                self.undo_games.push(redone_game);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// returns the replaced game, the hints and undos stay counted in the new one
+fn switch_game(game: &mut Game, new_game: Game, undos_used: usize) -> Game {
+    let hints_used = game.hints_used;
+    let replaced_game = std::mem::replace(game, new_game);
+    game.hints_used = hints_used;
+    game.undos_used = undos_used;
+    replaced_game
+}
@@ -1,94 +1,88 @@
-use crossterm::event::{
-    KeyEvent, KeyEventKind, KeyEventState, KeyModifiers, MouseButton,
-    MouseEventKind,
-};
-use crossterm::{
-    event::{read, Event, KeyCode},
-};
+use crossterm::event::{Event, KeyModifiers, MouseButton, MouseEventKind};
 use std::io::{Error, ErrorKind, Result};
 
-use crate::board::Board;
+use crate::board::{Board, Overlay};
+use crate::keys::{key_from_event, KeyAction};
 
-const CTRL_C_KEY: KeyEvent = KeyEvent {
-    code: KeyCode::Char('c'),
-    modifiers: KeyModifiers::CONTROL,
-    kind: KeyEventKind::Press,
-    state: KeyEventState::NONE,
-};
-const Q_KEY: KeyEvent = KeyEvent {
-    code: KeyCode::Char('q'),
-    modifiers: KeyModifiers::NONE,
-    kind: KeyEventKind::Press,
-    state: KeyEventState::NONE,
-};
-const ESC_KEY: KeyEvent = KeyEvent {
-    code: KeyCode::Esc,
-    modifiers: KeyModifiers::NONE,
-    kind: KeyEventKind::Press,
-    state: KeyEventState::NONE,
-};
-const TAB_KEY: KeyEvent = KeyEvent {
-    code: KeyCode::Tab,
-    modifiers: KeyModifiers::NONE,
-    kind: KeyEventKind::Press,
-    state: KeyEventState::NONE,
-};
-const T_KEY: KeyEvent = KeyEvent {
-    code: KeyCode::Char('t'),
-    modifiers: KeyModifiers::NONE,
-    kind: KeyEventKind::Press,
-    state: KeyEventState::NONE,
-};
-const H_KEY: KeyEvent = KeyEvent {
-    code: KeyCode::Char('h'),
-    modifiers: KeyModifiers::NONE,
-    kind: KeyEventKind::Press,
-    state: KeyEventState::NONE,
-};
-
-pub fn process_input(game_board: &mut Board) -> Result<()>  {
-    let event = read()?;
+// during the losing animation, only exit and undo are accepted
+pub fn process_animation_input(game_board: &mut Board, event: Event) -> Result<()> {
+    if let Some(key) = key_from_event(&event) {
+        match game_board.key_bindings.action(&key) {
+            Some(KeyAction::Quit) => return Err(Error::new(ErrorKind::Interrupted, "")),
+            Some(KeyAction::Undo) => game_board.undo(),
+            _ => {}
+        }
+    }
+    Ok(())
+}
 
+pub fn process_input(game_board: &mut Board, event: Event) -> Result<()> {
     if let Event::Mouse(mouse_event) = event {
+        // the board is hidden behind an overlay
+        if game_board.overlay().is_some() {
+            return Ok(());
+        }
         let row = mouse_event.row as usize; // TODO: usize::try_from(mouse_event.row);
         let column = mouse_event.column as usize; // TODO: usize::try_from(mouse_event.column);
 
         if mouse_event.kind == MouseEventKind::Moved {
             game_board.mouse_hover(row, column);
         }
-        if mouse_event.kind == MouseEventKind::Down(MouseButton::Left) {
-            game_board.mouse_down(row, column, true);
-        } else if mouse_event.kind == MouseEventKind::Down(MouseButton::Right)
-            || mouse_event.kind == MouseEventKind::Down(MouseButton::Middle)
-        {
-            game_board.mouse_down(row, column, false);
+        // pan the board with the mouse wheel, shift turns it sideways
+        let sideways = mouse_event.modifiers.contains(KeyModifiers::SHIFT);
+        match mouse_event.kind {
+            MouseEventKind::ScrollUp if sideways => game_board.pan(0, -1),
+            MouseEventKind::ScrollDown if sideways => game_board.pan(0, 1),
+            MouseEventKind::ScrollUp => game_board.pan(-1, 0),
+            MouseEventKind::ScrollDown => game_board.pan(1, 0),
+            MouseEventKind::ScrollLeft => game_board.pan(0, -1),
+            MouseEventKind::ScrollRight => game_board.pan(0, 1),
+            _ => {}
+        }
+        // a middle click, or the left and right buttons together, chord
+        match mouse_event.kind {
+            MouseEventKind::Down(MouseButton::Left) => game_board.mouse_down(row, column, true),
+            MouseEventKind::Down(MouseButton::Right) => game_board.mouse_down(row, column, false),
+            MouseEventKind::Down(MouseButton::Middle) => game_board.mouse_chord(row, column),
+            MouseEventKind::Up(MouseButton::Left) => game_board.mouse_up(true),
+            MouseEventKind::Up(MouseButton::Right) => game_board.mouse_up(false),
+            _ => {}
         }
     }
 
-    if let Event::Key(key_event) = event {
-        // exit on CTRL_C, ESC, or Q
-        if key_event == CTRL_C_KEY || key_event == ESC_KEY || key_event == Q_KEY {
-            return Err(Error::new(ErrorKind::Interrupted, ""));
-        }
-        // change theme on TAB or T
-        if key_event == TAB_KEY || key_event == T_KEY {
-            game_board.change_theme();
-        }
-        // discover a non-bomb on H
-        if key_event == H_KEY {
-            game_board.hint();
-        }
+    if let Event::Resize(columns, rows) = event {
+        game_board.resize(rows as usize, columns as usize);
+    }
 
-        // Keyboard navigation
-        match key_event.code {
-            KeyCode::Up => game_board.move_selection(-1, 0),
-            KeyCode::Down => game_board.move_selection(1, 0),
-            KeyCode::Left => game_board.move_selection(0, -1),
-            KeyCode::Right => game_board.move_selection(0, 1),
-            KeyCode::Char('f') | KeyCode::Char('F') => game_board.flag_selected(),
-            KeyCode::Char('c') | KeyCode::Char('C') => game_board.change_theme_color(),
-            KeyCode::Enter | KeyCode::Char(' ') => game_board.open_selected(),
-            _ => {}
+    if let Some(key) = key_from_event(&event) {
+        let action = game_board.key_bindings.action(&key);
+        // any other key closes the overlay, without reaching the board
+        if game_board.overlay().is_some() && action != Some(KeyAction::Quit) {
+            game_board.close_overlay();
+            return Ok(());
+        }
+        match action {
+            Some(KeyAction::Quit) => return Err(Error::new(ErrorKind::Interrupted, "")),
+            Some(KeyAction::MoveUp) => game_board.move_selection(-1, 0),
+            Some(KeyAction::MoveDown) => game_board.move_selection(1, 0),
+            Some(KeyAction::MoveLeft) => game_board.move_selection(0, -1),
+            Some(KeyAction::MoveRight) => game_board.move_selection(0, 1),
+            Some(KeyAction::PanUp) => game_board.pan(-1, 0),
+            Some(KeyAction::PanDown) => game_board.pan(1, 0),
+            Some(KeyAction::PanLeft) => game_board.pan(0, -1),
+            Some(KeyAction::PanRight) => game_board.pan(0, 1),
+            Some(KeyAction::Reveal) => game_board.open_selected(),
+            Some(KeyAction::Flag) => game_board.flag_selected(),
+            Some(KeyAction::Chord) => game_board.chord_selected(),
+            Some(KeyAction::Hint) => game_board.hint(),
+            Some(KeyAction::Probabilities) => game_board.toggle_bomb_probabilities(),
+            Some(KeyAction::Theme) => game_board.change_theme(),
+            Some(KeyAction::Colour) => game_board.change_theme_color(),
+            Some(KeyAction::Undo) => game_board.undo(),
+            Some(KeyAction::Redo) => game_board.redo(),
+            Some(KeyAction::Help) => game_board.open_overlay(Overlay::Help),
+            Some(KeyAction::Pause) => game_board.open_overlay(Overlay::Pause),
+            None => {}
         }
     }
 
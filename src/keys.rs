@@ -0,0 +1,479 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+
+// what a key does in the game
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KeyAction {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    Reveal,
+    Flag,
+    Chord,
+    Hint,
+    Probabilities,
+    Theme,
+    Colour,
+    Undo,
+    Redo,
+    Help,
+    Pause,
+    Quit,
+}
+
+// in the order of the help, with the names of the config file and the description
+const KEY_ACTIONS: [(KeyAction, &str, &str); 20] = [
+    (KeyAction::MoveUp, "move_up", "Move the selection up"),
+    (KeyAction::MoveDown, "move_down", "Move the selection down"),
+    (KeyAction::MoveLeft, "move_left", "Move the selection left"),
+    (
+        KeyAction::MoveRight,
+        "move_right",
+        "Move the selection right",
+    ),
+    (KeyAction::PanUp, "pan_up", "Scroll the board up"),
+    (KeyAction::PanDown, "pan_down", "Scroll the board down"),
+    (KeyAction::PanLeft, "pan_left", "Scroll the board left"),
+    (KeyAction::PanRight, "pan_right", "Scroll the board right"),
+    (KeyAction::Reveal, "reveal", "Open the selected cell"),
+    (KeyAction::Flag, "flag", "Flag the selected cell"),
+    (
+        KeyAction::Chord,
+        "chord",
+        "Open or flag the cells around the selected number",
+    ),
+    (
+        KeyAction::Hint,
+        "hint",
+        "Show a cell that can be proven safe, and why",
+    ),
+    (
+        KeyAction::Probabilities,
+        "probabilities",
+        "Show the chance of a bomb in every unknown cell",
+    ),
+    (KeyAction::Theme, "theme", "Change theme"),
+    (
+        KeyAction::Colour,
+        "colour",
+        "Change the colours of the numbers",
+    ),
+    (KeyAction::Undo, "undo", "Undo the last move"),
+    (KeyAction::Redo, "redo", "Redo the undone move"),
+    (KeyAction::Help, "help", "Show the keys and the rules"),
+    (
+        KeyAction::Pause,
+        "pause",
+        "Pause the game, the board is hidden and the clock stops",
+    ),
+    (KeyAction::Quit, "quit", "Exit the game"),
+];
+
+pub fn get_key_action(action_name: &str) -> Option<KeyAction> {
+    KEY_ACTIONS
+        .iter()
+        .find(|(_, name, _)| *name == action_name)
+        .map(|(action, _, _)| *action)
+}
+
+pub fn key_action_name(action: KeyAction) -> &'static str {
+    KEY_ACTIONS
+        .iter()
+        .find(|(key_action, _, _)| *key_action == action)
+        .map_or("", |(_, name, _)| name)
+}
+
+// a key with its modifiers; letters with shift are kept as upper case letters,
+// and letters with ctrl or alt as lower case ones
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+fn init_key(code: KeyCode, modifiers: KeyModifiers) -> Key {
+    match code {
+        KeyCode::Char(c) if modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+            Key {
+                code: KeyCode::Char(c.to_ascii_lowercase()),
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            }
+        }
+        KeyCode::Char(c) => Key {
+            code: KeyCode::Char(if modifiers.contains(KeyModifiers::SHIFT) {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }),
+            modifiers: modifiers - KeyModifiers::SHIFT,
+        },
+        _ => Key { code, modifiers },
+    }
+}
+
+// the key of a press or a repeat, the releases are skipped
+pub fn key_from_event(event: &Event) -> Option<Key> {
+    match event {
+        Event::Key(key_event) if key_event.kind != KeyEventKind::Release => {
+            Some(init_key(key_event.code, key_event.modifiers))
+        }
+        _ => None,
+    }
+}
+
+const KEY_NAMES: [(KeyCode, &str); 13] = [
+    (KeyCode::Up, "Up"),
+    (KeyCode::Down, "Down"),
+    (KeyCode::Left, "Left"),
+    (KeyCode::Right, "Right"),
+    (KeyCode::Enter, "Enter"),
+    (KeyCode::Char(' '), "Space"),
+    (KeyCode::Tab, "Tab"),
+    (KeyCode::Esc, "Esc"),
+    (KeyCode::Backspace, "Backspace"),
+    (KeyCode::Home, "Home"),
+    (KeyCode::End, "End"),
+    (KeyCode::PageUp, "PageUp"),
+    (KeyCode::PageDown, "PageDown"),
+];
+
+// a key like "h", "H", "?", "Space", "F1", "Shift+Up" or "Ctrl+z"
+pub fn get_key(key_name: &str) -> Option<Key> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = key_name;
+    // the last part is the key itself, so "Ctrl++" is ctrl and plus
+    while let Some((modifier, key)) = rest.split_once('+').filter(|(_, key)| !key.is_empty()) {
+        modifiers |= match modifier.to_ascii_lowercase().as_str() {
+            "ctrl" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return None,
+        };
+        rest = key;
+    }
+
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => {
+            if let Some(number) = rest
+                .strip_prefix(['F', 'f'])
+                .and_then(|number| number.parse::<u8>().ok())
+                .filter(|number| (1..=12).contains(number))
+            {
+                KeyCode::F(number)
+            } else {
+                KEY_NAMES
+                    .iter()
+                    .find(|(_, name)| name.eq_ignore_ascii_case(rest))
+                    .map(|(code, _)| *code)?
+            }
+        }
+    };
+    Some(init_key(code, modifiers))
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+        match self.code {
+            KeyCode::F(number) => write!(f, "F{}", number),
+            KeyCode::Char(c) if self.modifiers.is_empty() && c != ' ' => write!(f, "{}", c),
+            // the letters with ctrl or alt take either case
+            KeyCode::Char(c) if c != ' ' => write!(f, "{}", c.to_ascii_uppercase()),
+            code => match KEY_NAMES.iter().find(|(key_code, _)| *key_code == code) {
+                Some((_, name)) => write!(f, "{}", name),
+                None => write!(f, "{:?}", code),
+            },
+        }
+    }
+}
+
+// the keys of every action
+#[derive(Clone, PartialEq, Debug)]
+pub struct KeyBindings {
+    bindings: Vec<(KeyAction, Vec<Key>)>,
+}
+
+fn init_key_bindings(bindings: &[(KeyAction, &[&str])]) -> KeyBindings {
+    KeyBindings {
+        bindings: bindings
+            .iter()
+            .map(|(action, keys)| {
+                let keys = keys.iter().filter_map(|key| get_key(key)).collect();
+                (*action, keys)
+            })
+            .collect(),
+    }
+}
+
+// the keys of the actions that every preset shares
+const COMMON_KEYS: [(KeyAction, &[&str]); 11] = [
+    (KeyAction::Flag, &["f", "F"]),
+    (KeyAction::Chord, &["x", "X"]),
+    (KeyAction::Probabilities, &["o", "O"]),
+    (KeyAction::Theme, &["Tab", "t", "T"]),
+    (KeyAction::Colour, &["c", "C"]),
+    (KeyAction::Undo, &["u", "U", "Ctrl+z"]),
+    (KeyAction::Redo, &["Ctrl+r"]),
+    (KeyAction::Help, &["?", "F1"]),
+    (KeyAction::Pause, &["p", "P"]),
+    (KeyAction::Quit, &["Ctrl+c", "q", "Q", "Esc"]),
+    (KeyAction::Reveal, &["Enter", "Space"]),
+];
+
+// the arrow keys move in every preset, and pan with shift
+const ARROW_KEYS: [(KeyAction, &[&str]); 8] = [
+    (KeyAction::MoveUp, &["Up"]),
+    (KeyAction::MoveDown, &["Down"]),
+    (KeyAction::MoveLeft, &["Left"]),
+    (KeyAction::MoveRight, &["Right"]),
+    (KeyAction::PanUp, &["Shift+Up"]),
+    (KeyAction::PanDown, &["Shift+Down"]),
+    (KeyAction::PanLeft, &["Shift+Left"]),
+    (KeyAction::PanRight, &["Shift+Right"]),
+];
+
+// default: the arrow keys, vim: hjkl and HJKL to scroll, with the hint on n,
+// wasd: wasd and WASD to scroll, with the reveal also on e
+pub fn get_key_preset(preset_name: &str) -> Option<KeyBindings> {
+    let (moves, hint, reveal): ([&[&str]; 8], &[&str], &[&str]) = match preset_name {
+        "default" => ([&[]; 8], &["h", "H"], &[]),
+        "vim" => (
+            [
+                &["k"],
+                &["j"],
+                &["h"],
+                &["l"],
+                &["K"],
+                &["J"],
+                &["H"],
+                &["L"],
+            ],
+            &["n", "N"],
+            &[],
+        ),
+        "wasd" => (
+            [
+                &["w"],
+                &["s"],
+                &["a"],
+                &["d"],
+                &["W"],
+                &["S"],
+                &["A"],
+                &["D"],
+            ],
+            &["h", "H"],
+            &["e", "E"],
+        ),
+        _ => return None,
+    };
+    let mut bindings: Vec<(KeyAction, Vec<&str>)> = Vec::new();
+    for ((action, arrow_keys), preset_keys) in ARROW_KEYS.iter().zip(moves) {
+        bindings.push((*action, [*arrow_keys, preset_keys].concat()));
+    }
+    for (action, keys) in COMMON_KEYS {
+        let extra_keys = if action == KeyAction::Reveal {
+            reveal
+        } else {
+            &[]
+        };
+        bindings.push((action, [keys, extra_keys].concat()));
+    }
+    bindings.push((KeyAction::Hint, hint.to_vec()));
+    // in the order of the help
+    bindings.sort_by_key(|(action, _)| {
+        KEY_ACTIONS
+            .iter()
+            .position(|(key_action, _, _)| key_action == action)
+    });
+    let bindings: Vec<(KeyAction, &[&str])> = bindings
+        .iter()
+        .map(|(action, keys)| (*action, keys.as_slice()))
+        .collect();
+    Some(init_key_bindings(&bindings))
+}
+
+// the bindings of the default preset
+impl Default for KeyBindings {
+    fn default() -> Self {
+        get_key_preset("default").unwrap_or(KeyBindings {
+            bindings: Vec::new(),
+        })
+    }
+}
+
+impl KeyBindings {
+    pub fn action(&self, key: &Key) -> Option<KeyAction> {
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.contains(key))
+            .map(|(action, _)| *action)
+    }
+
+    // replaces the keys of some actions, like {"hint": ["i"]}
+    pub fn rebind(&mut self, custom_keys: &BTreeMap<String, Vec<String>>) -> Result<(), String> {
+        for (action_name, key_names) in custom_keys {
+            let action = get_key_action(action_name).ok_or_else(|| {
+                format!(
+                    "unknown action {} in the key bindings, use one of {}",
+                    action_name,
+                    KEY_ACTIONS.map(|(_, name, _)| name).join(", ")
+                )
+            })?;
+            let keys = key_names
+                .iter()
+                .map(|key_name| {
+                    get_key(key_name).ok_or_else(|| {
+                        format!(
+                            "unknown key {:?} for {}, use keys like \"h\", \"Space\", \"F1\" or \"Ctrl+z\"",
+                            key_name, action_name
+                        )
+                    })
+                })
+                .collect::<Result<Vec<Key>, String>>()?;
+            match self.bindings.iter_mut().find(|(a, _)| *a == action) {
+                Some((_, bound_keys)) => *bound_keys = keys,
+                None => self.bindings.push((action, keys)),
+            }
+        }
+        self.check()
+    }
+
+    // a key can only do one thing
+    pub fn check(&self) -> Result<(), String> {
+        for (index, (action, keys)) in self.bindings.iter().enumerate() {
+            for key in keys {
+                if let Some((other_action, _)) = self.bindings[index + 1..]
+                    .iter()
+                    .find(|(_, other_keys)| other_keys.contains(key))
+                {
+                    return Err(format!(
+                        "the key {} is bound to both {} and {}, change one of them in the key bindings",
+                        key,
+                        key_action_name(*action),
+                        key_action_name(*other_action)
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // one line for every action with its keys, and one for the mouse
+    pub fn format_help(&self) -> Vec<String> {
+        let lines: Vec<(String, &str)> = KEY_ACTIONS
+            .iter()
+            .map(|(action, _, description)| {
+                let keys = self
+                    .bindings
+                    .iter()
+                    .find(|(a, _)| a == action)
+                    .map_or(Vec::new(), |(_, keys)| {
+                        keys.iter().map(|key| key.to_string()).collect()
+                    });
+                (keys.join(", "), *description)
+            })
+            .collect();
+        let width = lines.iter().map(|(keys, _)| keys.len()).max().unwrap_or(0);
+        let mut help: Vec<String> = lines
+            .into_iter()
+            .map(|(keys, description)| {
+                let keys = if keys.is_empty() {
+                    "-".to_owned()
+                } else {
+                    keys
+                };
+                format!("{:width$}  {}", keys, description, width = width)
+            })
+            .collect();
+        help.push(
+            "Mouse: left click opens, right click flags, middle or left+right click chords"
+                .to_owned(),
+        );
+        help
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyEvent;
+
+    use super::*;
+
+    #[test]
+    fn keys_are_parsed_and_printed() {
+        for key_name in [
+            "h", "H", "?", "Space", "F1", "Shift+Up", "Ctrl+Z", "Tab", "+",
+        ] {
+            assert_eq!(get_key(key_name).unwrap().to_string(), key_name);
+        }
+        assert_eq!(get_key("Shift+h"), get_key("H"));
+        assert_eq!(get_key("ctrl+z"), get_key("Ctrl+Z"));
+        assert_eq!(get_key("F13"), None);
+        assert_eq!(get_key("Hyper+h"), None);
+
+        let shift_h = KeyEvent::new(KeyCode::Char('H'), KeyModifiers::SHIFT);
+        assert_eq!(key_from_event(&Event::Key(shift_h)), get_key("H"));
+    }
+
+    #[test]
+    fn presets_have_no_conflicts() {
+        for preset_name in ["default", "vim", "wasd"] {
+            let key_bindings = get_key_preset(preset_name).unwrap();
+            assert_eq!(key_bindings.check(), Ok(()));
+            assert_eq!(
+                key_bindings.action(&get_key("Up").unwrap()),
+                Some(KeyAction::MoveUp)
+            );
+        }
+        let vim = get_key_preset("vim").unwrap();
+        assert_eq!(
+            vim.action(&get_key("h").unwrap()),
+            Some(KeyAction::MoveLeft)
+        );
+        assert_eq!(vim.action(&get_key("n").unwrap()), Some(KeyAction::Hint));
+        assert_eq!(get_key_preset("emacs"), None);
+    }
+
+    #[test]
+    fn rebinding_detects_conflicts() {
+        let mut key_bindings = get_key_preset("vim").unwrap();
+        let custom_keys = BTreeMap::from([("hint".to_owned(), vec!["h".to_owned()])]);
+        assert_eq!(
+            key_bindings.rebind(&custom_keys),
+            Err(
+                "the key h is bound to both move_left and hint, change one of them in the key bindings"
+                    .to_owned()
+            )
+        );
+
+        let mut key_bindings = get_key_preset("default").unwrap();
+        let custom_keys = BTreeMap::from([("hint".to_owned(), vec!["i".to_owned()])]);
+        assert_eq!(key_bindings.rebind(&custom_keys), Ok(()));
+        assert_eq!(
+            key_bindings.action(&get_key("i").unwrap()),
+            Some(KeyAction::Hint)
+        );
+        assert_eq!(key_bindings.action(&get_key("h").unwrap()), None);
+
+        let custom_keys = BTreeMap::from([("dig".to_owned(), vec!["d".to_owned()])]);
+        assert!(key_bindings.rebind(&custom_keys).is_err());
+    }
+}
@@ -0,0 +1,10 @@
+// the game rules, usable without the terminal front-end
+pub mod bench;
+pub mod game;
+pub mod game_code;
+pub mod history;
+pub mod protocol;
+pub mod replay;
+pub mod save;
+pub mod solver;
+pub mod stats;
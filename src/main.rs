@@ -1,42 +1,193 @@
-use clap::Parser;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
 use crossterm::event::{
     KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
 };
 use crossterm::{
-    event::{poll, read, DisableMouseCapture, EnableMouseCapture},
+    event::{
+        poll, read, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind,
+        KeyModifiers,
+    },
     execute, queue,
-    terminal::{disable_raw_mode, enable_raw_mode},
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use std::io::{stdout, Result, Stdout};
+use std::collections::BTreeMap;
+use std::io::{stdin, stdout, ErrorKind, Result, Stdout};
+use std::path::PathBuf;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+use minesweeper::bench::{format_bench_report, run_bench};
+use minesweeper::game::{
+    check_game_code, get_difficulty, get_first_click_rule, get_neighbourhood, get_topology,
+    init_game_from_code, GameStatus, Neighbourhood, Topology,
+};
+use minesweeper::game_code::GameCode;
+use minesweeper::protocol::{init_session, serve};
+use minesweeper::replay::{check_replay, read_replay, write_replay};
+use minesweeper::save::{read_saved_game, save_path, write_saved_game};
+use minesweeper::stats::{append_record, format_report, read_records, record_game, stats_path};
 
 mod board;
-use board::{init_random_game, Board};
+use board::{format_elapsed, get_chord_mode, init_board, init_board_from_saved_game, Board};
+
+mod config;
+use config::{config_path, format_settings, read_config, Config, Setting, Source};
 
 mod theme;
-use theme::get_theme;
+use theme::{
+    get_palette, load_theme, read_theme_file, theme_files, themes_dir, BUILT_IN_THEME_NAMES,
+};
+
+mod keys;
+use keys::get_key_preset;
 
 mod input;
-use input::process_input;
+use input::{process_animation_input, process_input};
+
+mod playback;
+use playback::{init_playback, playback_loop};
+
+mod screen;
 
 use crate::board::GameCompletionStatus;
 
-fn event_loop(mut game_board: Board, stdout: &Stdout) -> Result<()> {
+// where a game is saved when there is no data directory
+const FALLBACK_SAVE_PATH: &str = "minesweeper-save.json";
+
+// asks before leaving an unfinished game, so it can be continued with --resume,
+// a game that is already lost is not saved
+fn ask_to_save(game_board: &Board, save_path: &str) -> Result<()> {
+    if game_board.game.status != GameStatus::OnGoing {
+        return Ok(());
+    }
+    println!("save the game to {}? (y/n)\r", save_path);
+    loop {
+        if let Event::Key(key_event) = read()? {
+            if key_event.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key_event.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                    match write_saved_game(save_path, &game_board.to_saved_game()) {
+                        Ok(()) => println!("game saved, continue it with --resume {}\r", save_path),
+                        Err(e) => println!("{}\r", e),
+                    }
+                    return Ok(());
+                }
+                KeyCode::Char('c') if key_event.modifiers == KeyModifiers::CONTROL => return Ok(()),
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Char('q') | KeyCode::Esc => {
+                    return Ok(())
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// the game is drawn on the alternate screen, its last frame is kept on the normal screen
+fn leave_game_screen(game_board: &Board, mut stdout: &Stdout) -> Result<()> {
+    execute!(stdout, LeaveAlternateScreen)?;
+    game_board.print_shown_frame();
+    Ok(())
+}
+
+// adds the finished game to the stats file
+fn save_record(game_board: &Board) {
+    if let Some(path) = stats_path() {
+        let record = record_game(&game_board.game, game_board.elapsed());
+        if let Err(e) = append_record(&path, &record) {
+            println!("{}\r", e);
+        }
+    }
+}
+
+fn print_stats() {
+    match stats_path() {
+        Some(path) => match read_records(&path) {
+            Ok(records) => print!("{}", format_report(&records)),
+            Err(e) => println!("{}", e),
+        },
+        None => println!("could not find the data directory for the stats"),
+    }
+}
+
+// the built-in themes, then the theme files with the reason when they can not be used
+fn print_themes() {
+    for name in BUILT_IN_THEME_NAMES {
+        println!("{}", name);
+    }
+    for (name, path) in theme_files() {
+        match read_theme_file(&path) {
+            Ok(_) if BUILT_IN_THEME_NAMES.contains(&name.as_str()) => println!(
+                "{} ({}): hidden by the built-in theme with the same name",
+                name,
+                path.display()
+            ),
+            Ok(_) => println!("{} ({})", name, path.display()),
+            Err(e) => println!("{}: {}", name, e),
+        }
+    }
+    if let Some(directory) = themes_dir() {
+        println!("add themes as toml files in {}", directory.display());
+    }
+}
+
+fn event_loop(game_board: &mut Board, stdout: &Stdout, save_path: &str) -> Result<()> {
     // first draw
-    game_board.draw(&stdout)?;
+    game_board.draw(stdout)?;
     game_board.update();
 
     loop {
         if game_board.game_completion_status == GameCompletionStatus::GameIsOnGoing {
-            process_input(&mut game_board)?;
+            // wait for the input, but wake up to redraw the clock
+            let event = match game_board.time_until_next_second() {
+                Some(timeout) => {
+                    if poll(timeout)? {
+                        Some(read()?)
+                    } else {
+                        None
+                    }
+                }
+                None => Some(read()?),
+            };
+            if let Some(event) = event {
+                if let Err(e) = process_input(game_board, event) {
+                    if e.kind() == ErrorKind::Interrupted {
+                        leave_game_screen(game_board, stdout)?;
+                        ask_to_save(game_board, save_path)?;
+                    }
+                    return Err(e);
+                }
+            }
+            game_board.update_clock();
         }
 
         if let Some(time) = game_board.delay_before_draw {
-            thread::sleep(time);
+            if let GameCompletionStatus::LostAnimation(_, _) = game_board.game_completion_status {
+                // the losing reveal can be undone until the animation ends
+                let deadline = Instant::now() + time;
+                while let Some(timeout) = deadline.checked_duration_since(Instant::now()) {
+                    if !poll(timeout)? {
+                        break;
+                    }
+                    if let Err(e) = process_animation_input(game_board, read()?) {
+                        if e.kind() == ErrorKind::Interrupted {
+                            leave_game_screen(game_board, stdout)?;
+                            ask_to_save(game_board, save_path)?;
+                        }
+                        return Err(e);
+                    }
+                    if game_board.game_completion_status == GameCompletionStatus::GameIsOnGoing {
+                        break;
+                    }
+                }
+            } else {
+                thread::sleep(time);
+            }
         }
 
-        game_board.draw(&stdout)?;
+        game_board.draw(stdout)?;
         game_board.update();
 
         if game_board.game_completion_status == GameCompletionStatus::CompletedAsLost
@@ -53,11 +204,24 @@ fn event_loop(mut game_board: Board, stdout: &Stdout) -> Result<()> {
             break;
         }
     }
+    leave_game_screen(game_board, stdout)?;
+    // games with undo are practice, not competitive play
+    let undo_note = match game_board.game.undos_used {
+        0 => String::new(),
+        1 => " (practice, used undo once)".to_owned(),
+        undos_used => format!(" (practice, used undo {} times)", undos_used),
+    };
     if game_board.game_completion_status == GameCompletionStatus::CompletedAsLost {
-        println!("Boom! You Lost :(\r");
+        println!("Boom! You Lost :({}\r", undo_note);
     } else if game_board.game_completion_status == GameCompletionStatus::CompletedAsWin {
-        println!("You Won :)\r");
+        println!(
+            "You Won :) time: {}{}\r",
+            format_elapsed(game_board.elapsed()),
+            undo_note
+        );
     }
+    println!("game code: {}\r", game_board.game.game_code);
+    save_record(game_board);
 
     Ok(())
 }
@@ -72,11 +236,22 @@ See https://github.com/mohsenasm/rust-minesweeper
 Key                          | Action
 ---------------------------- | -----------
 Mouse, Arrow keys            | Navigate the board
+Wheel, Shift+Arrow keys      | Scroll a board larger than the terminal
 Left Click, Enter, Space     | Open the selected cell
-Right Click, Middle Click, F | Flag the selected cell
+Right Click, F               | Flag the selected cell
+X, Middle Click              | Chord: open or flag the cells around the selected number
 Tab, T                       | Change theme
-H                            | Show a hint
+C                            | Change the colours of the numbers
+H                            | Show a cell that can be proven safe, and why
+O                            | Show the chance of a bomb in every unknown cell
+U, Ctrl+Z                    | Undo the last move
+Ctrl+R                       | Redo the undone move
+?, F1                        | Show the rules and the keys in use
+P                            | Pause, the board is hidden and the clock stops
 Ctrl+C, Q, Esc               | Exit the game
+
+These are the keys of the default preset, the vim and wasd presets and the key_bindings
+of the config file change them, press ? or F1 in the game for the keys in use.
 "
 )]
 struct Args {
@@ -88,60 +263,473 @@ struct Args {
     #[arg(short, long, default_value_t = 0.2)]
     bomb_percentage: f32,
 
-    /// The board theme (border, dark_border, borderless)
+    /// The exact number of bombs, instead of the bomb percentage
+    #[arg(short, long, conflicts_with = "bomb_percentage")]
+    mines: Option<usize>,
+
+    /// A standard board (beginner: 9x9 with 10 bombs, intermediate: 16x16 with 40 bombs,
+    /// expert: 30x16 with 99 bombs)
+    #[arg(short, long, conflicts_with_all = ["size", "bomb_percentage", "mines"])]
+    difficulty: Option<String>,
+
+    /// The board theme (border, dark_border, borderless, or the name of a theme file)
     #[arg(short, long, default_value = "dark_border")]
     theme: String,
+
+    /// Print the built-in themes and the theme files
+    #[arg(long)]
+    list_themes: bool,
+
+    /// The colours of the numbers (classic, pastel, none), the colours of the theme by default
+    #[arg(long)]
+    palette: Option<String>,
+
+    /// The key preset (default: arrow keys, vim: hjkl and the hint on n, wasd: wasd and the
+    /// reveal also on e)
+    #[arg(long, default_value = "default")]
+    keys: String,
+
+    /// How a chord on a number works (classic: it opens the neighbours once the number has as
+    /// many flags, smart: it also flags the neighbours that can only be bombs, and a click on a
+    /// number chords)
+    #[arg(long, default_value = "smart")]
+    chord: String,
+
+    /// The first click rule (off: open a starting cell, safe: the first click is never a bomb,
+    /// zero: the first click opens an empty area)
+    #[arg(short, long, default_value = "off")]
+    first_click: String,
+
+    /// Only generate boards that can be solved from the starting cell without guessing
+    #[arg(short, long, conflicts_with = "first_click")]
+    no_guess: bool,
+
+    /// The shape of the cells (square: 8 neighbours, hex: 6 neighbours in shifted rows)
+    #[arg(long, default_value = "square")]
+    topology: String,
+
+    /// Which cells count as neighbours on a square board (standard, cross, knight, radius2,
+    /// or steps as rows,columns like "1,2;2,1;-1,-2")
+    #[arg(long, default_value = "standard", allow_hyphen_values = true)]
+    neighbourhood: String,
+
+    /// How many mines a cell can hold (1 to 9), the numbers count the mines and a cell takes as many
+    /// flags
+    #[arg(long, default_value_t = 1)]
+    max_mines_per_cell: usize,
+
+    /// Join the edges to the opposite edges, so the cells at the edges have all their neighbours
+    #[arg(short, long)]
+    wrap: bool,
+
+    /// The random seed, the same seed and options give the same board
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Replay the board of a game code printed at the end of a game
+    #[arg(short, long, conflicts_with_all = ["size", "bomb_percentage", "mines", "difficulty", "first_click", "no_guess", "topology", "neighbourhood", "max_mines_per_cell", "wrap", "seed"])]
+    code: Option<String>,
+
+    /// Record the moves of the game to a replay file
+    #[arg(long, value_name = "FILE")]
+    record: Option<String>,
+
+    /// Continue a game saved on exit
+    #[arg(short, long, value_name = "FILE", conflicts_with_all = ["size", "bomb_percentage", "mines", "difficulty", "theme", "first_click", "no_guess", "topology", "neighbourhood", "max_mines_per_cell", "wrap", "seed", "code"])]
+    resume: Option<String>,
+
+    /// Play through json lines on stdin and stdout instead of the terminal board (json)
+    #[arg(long, value_name = "FORMAT", conflicts_with_all = ["record", "resume"])]
+    protocol: Option<String>,
+
+    /// The config file with the defaults of the options [default: ~/.config/minesweeper/config.toml]
+    #[arg(long, value_name = "FILE")]
+    config: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+#[derive(Subcommand)]
+enum Command {
+    /// Print the win rate, streaks and best times of the finished games
+    Stats,
+    /// Play a game recorded with --record
+    Replay {
+        /// The replay file
+        file: String,
 
-    let size: Option<(usize, usize)> = {
-        let size_str = args.size.split('x').collect::<Vec<&str>>();
-        if size_str.len() != 2 {
-            println!("wrong size argument {}, enter it like 10x5\r", args.size);
+        /// The playback speed, 2 plays twice as fast
+        #[arg(long, default_value_t = 1.0)]
+        speed: f64,
+    },
+    /// Show the config file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Play many games with a solver bot and print how it did, without the terminal board
+    Bench {
+        /// The board size
+        #[arg(short, long, default_value = "30x16")]
+        size: String,
+
+        /// The number of bombs
+        #[arg(short, long, default_value_t = 99)]
+        mines: usize,
+
+        /// The number of games to play
+        #[arg(short, long, default_value_t = 1000)]
+        games: usize,
+
+        /// The number of threads, all the cores by default
+        #[arg(long)]
+        threads: Option<usize>,
+
+        /// The first click rule (off, safe, zero)
+        #[arg(short, long, default_value = "safe")]
+        first_click: String,
+
+        /// The seed of the first game, the next games use the following seeds
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the options with the values of the command line, the config file and the defaults
+    Show,
+}
+
+// the options of the config file that are not given on the command line take their place,
+// unless the command line has an option they conflict with
+fn apply_config(args: &mut Args, matches: &ArgMatches, config: Config) -> Vec<Setting> {
+    let from_command_line = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    let mut settings = Vec::new();
+    macro_rules! apply {
+        ($field:ident, $conflicts:expr, $set:expr, $format:expr) => {
+            let source = if from_command_line(stringify!($field)) {
+                Source::CommandLine
+            } else {
+                match config.$field {
+                    Some(value) if !$conflicts.iter().any(|id: &&str| from_command_line(id)) => {
+                        $set(&mut *args, value);
+                        Source::ConfigFile
+                    }
+                    _ => Source::Default,
+                }
+            };
+            settings.push(Setting {
+                name: stringify!($field),
+                value: $format(&*args),
+                source,
+            });
+        };
+    }
+    let text = |value: &str| Some(format!("{:?}", value));
+    apply!(
+        size,
+        ["difficulty"],
+        |args: &mut Args, value| args.size = value,
+        |args: &Args| text(&args.size)
+    );
+    apply!(
+        bomb_percentage,
+        ["mines", "difficulty"],
+        |args: &mut Args, value| args.bomb_percentage = value,
+        |args: &Args| Some(args.bomb_percentage.to_string())
+    );
+    apply!(
+        mines,
+        ["bomb_percentage", "difficulty"],
+        |args: &mut Args, value| args.mines = Some(value),
+        |args: &Args| args.mines.map(|mines| mines.to_string())
+    );
+    apply!(
+        difficulty,
+        ["size", "bomb_percentage", "mines"],
+        |args: &mut Args, value| args.difficulty = Some(value),
+        |args: &Args| args.difficulty.as_deref().and_then(text)
+    );
+    apply!(
+        theme,
+        [],
+        |args: &mut Args, value| args.theme = value,
+        |args: &Args| text(&args.theme)
+    );
+    apply!(
+        palette,
+        [],
+        |args: &mut Args, value| args.palette = Some(value),
+        |args: &Args| args.palette.as_deref().and_then(text)
+    );
+    apply!(
+        keys,
+        [],
+        |args: &mut Args, value| args.keys = value,
+        |args: &Args| text(&args.keys)
+    );
+    apply!(
+        chord,
+        [],
+        |args: &mut Args, value| args.chord = value,
+        |args: &Args| text(&args.chord)
+    );
+    apply!(
+        first_click,
+        ["no_guess"],
+        |args: &mut Args, value| args.first_click = value,
+        |args: &Args| text(&args.first_click)
+    );
+    apply!(
+        no_guess,
+        ["first_click"],
+        |args: &mut Args, value| args.no_guess = value,
+        |args: &Args| Some(args.no_guess.to_string())
+    );
+    apply!(
+        topology,
+        [],
+        |args: &mut Args, value| args.topology = value,
+        |args: &Args| text(&args.topology)
+    );
+    apply!(
+        neighbourhood,
+        [],
+        |args: &mut Args, value| args.neighbourhood = value,
+        |args: &Args| text(&args.neighbourhood)
+    );
+    apply!(
+        max_mines_per_cell,
+        [],
+        |args: &mut Args, value| args.max_mines_per_cell = value,
+        |args: &Args| Some(args.max_mines_per_cell.to_string())
+    );
+    apply!(
+        wrap,
+        [],
+        |args: &mut Args, value| args.wrap = value,
+        |args: &Args| Some(args.wrap.to_string())
+    );
+    apply!(
+        seed,
+        [],
+        |args: &mut Args, value| args.seed = Some(value),
+        |args: &Args| args.seed.map(|seed| seed.to_string())
+    );
+    settings
+}
+
+// the key_bindings of the config file as an inline toml table
+fn format_key_bindings(custom_keys: &BTreeMap<String, Vec<String>>) -> String {
+    let actions: Vec<String> = custom_keys
+        .iter()
+        .map(|(action_name, key_names)| format!("{} = {:?}", action_name, key_names))
+        .collect();
+    format!("{{ {} }}", actions.join(", "))
+}
+
+fn parse_size(size: &str) -> Option<(usize, usize)> {
+    let size_str = size.split('x').collect::<Vec<&str>>();
+    if size_str.len() != 2 {
+        println!("wrong size argument {}, enter it like 10x5\r", size);
+        None
+    } else {
+        let width_parsed = size_str[0].parse::<usize>();
+        let height_parsed = size_str[1].parse::<usize>();
+        if let Err(ref e) = width_parsed {
+            println!("wrong size argument {}, enter it like 10x5\r", size);
+            println!("error detail: {}\r", e);
+            None
+        } else if let Err(ref e) = height_parsed {
+            println!("wrong size argument {}, enter it like 10x5\r", size);
+            println!("error detail: {}\r", e);
             None
         } else {
-            let width_parsed = size_str[0].parse::<usize>();
-            let height_parsed = size_str[1].parse::<usize>();
-            if let Err(ref e) = width_parsed {
-                println!("wrong size argument {}, enter it like 10x5\r", args.size);
-                println!("error detail: {}\r", e.to_string());
-                None
-            } else if let Err(ref e) = height_parsed {
-                println!("wrong size argument {}, enter it like 10x5\r", args.size);
-                println!("error detail: {}\r", e.to_string());
+            let width = width_parsed.unwrap();
+            let height = height_parsed.unwrap();
+
+            if width == 0 || height == 0 {
+                println!("wrong size argument {}, enter it like 10x5\r", size);
+                println!("error detail: the number is zero\r");
                 None
             } else {
-                let width = width_parsed.unwrap();
-                let height = height_parsed.unwrap();
-
-                if width == 0 || height == 0 {
-                    println!("wrong size argument {}, enter it like 10x5\r", args.size);
-                    println!("error detail: the number is zero\r");
-                    None
-                } else {
-                    Some((width, height))
-                }
+                Some((width, height))
+            }
+        }
+    }
+}
+
+fn game_code_from_args(args: &Args) -> Option<GameCode> {
+    if let Some(code) = &args.code {
+        return match code.parse::<GameCode>() {
+            Ok(game_code) => Some(game_code),
+            Err(e) => {
+                println!("{}\r", e);
+                None
             }
+        };
+    }
+
+    let (width, height, number_of_bombs) = if let Some(difficulty_name) = &args.difficulty {
+        let difficulty = get_difficulty(difficulty_name);
+        if difficulty.is_none() {
+            println!("not found difficulty {}\r", difficulty_name);
+            return None;
         }
+        let ((height, width), number_of_bombs) = difficulty.unwrap();
+        (width, height, number_of_bombs)
+    } else {
+        let (width, height) = parse_size(&args.size)?;
+        if !(0.0..=1.0).contains(&args.bomb_percentage) {
+            println!(
+                "wrong bomb percentage {}, enter a number between 0 and 1\r",
+                args.bomb_percentage
+            );
+            return None;
+        }
+        let number_of_bombs = args
+            .mines
+            .unwrap_or_else(|| (((width * height) as f32) * args.bomb_percentage).round() as usize);
+        (width, height, number_of_bombs)
     };
 
-    if size == None {
-        return Ok(());
+    let first_click_rule = get_first_click_rule(&args.first_click);
+    if first_click_rule.is_none() {
+        println!("not found first click rule {}\r", &args.first_click);
+        return None;
+    }
+
+    let topology = get_topology(&args.topology);
+    if topology.is_none() {
+        println!("not found topology {}\r", &args.topology);
+        return None;
+    }
+
+    let neighbourhood = get_neighbourhood(&args.neighbourhood);
+    if neighbourhood.is_none() {
+        println!("not found neighbourhood {}\r", &args.neighbourhood);
+        return None;
+    }
+
+    Some(GameCode {
+        size: (height, width),
+        number_of_bombs,
+        first_click_rule: first_click_rule.unwrap(),
+        no_guess: args.no_guess,
+        topology: topology.unwrap(),
+        neighbourhood: neighbourhood.unwrap(),
+        max_bombs_per_cell: args.max_mines_per_cell,
+        wrap: args.wrap,
+        seed: args.seed.unwrap_or_else(rand::random),
+    })
+}
+
+fn board_from_args(args: &Args) -> Option<Board> {
+    if let Some(path) = &args.resume {
+        let game_board =
+            read_saved_game(path).and_then(|saved_game| init_board_from_saved_game(&saved_game));
+        return match game_board {
+            Ok(game_board) => Some(game_board),
+            Err(e) => {
+                println!("{}\r", e);
+                None
+            }
+        };
+    }
+
+    let game_code = game_code_from_args(args)?;
+    if let Err(e) = check_game_code(&game_code) {
+        println!("{}\r", e);
+        return None;
+    }
+
+    let theme = match load_theme(&args.theme) {
+        Ok(theme) => theme,
+        Err(e) => {
+            println!("{}\r", e);
+            return None;
+        }
+    };
+
+    let game = match init_game_from_code(&game_code) {
+        Ok(game) => game,
+        Err(e) => {
+            println!("{}\r", e);
+            return None;
+        }
+    };
+    Some(init_board(game, theme))
+}
+
+fn run_bench_command(
+    size: &str,
+    mines: usize,
+    games: usize,
+    threads: Option<usize>,
+    first_click: &str,
+    seed: Option<u64>,
+) {
+    let (width, height) = match parse_size(size) {
+        Some(size) => size,
+        None => return,
+    };
+    let first_click_rule = match get_first_click_rule(first_click) {
+        Some(first_click_rule) => first_click_rule,
+        None => {
+            println!("not found first click rule {}", first_click);
+            return;
+        }
+    };
+    let game_code = GameCode {
+        size: (height, width),
+        number_of_bombs: mines,
+        first_click_rule,
+        no_guess: false,
+        topology: Topology::Square,
+        neighbourhood: Neighbourhood::Standard,
+        max_bombs_per_cell: 1,
+        wrap: false,
+        seed: seed.unwrap_or_else(rand::random),
+    };
+    if let Err(e) = check_game_code(&game_code) {
+        println!("{}", e);
+        return;
+    }
+    let threads = threads.unwrap_or_else(|| {
+        thread::available_parallelism()
+            .map(|threads| threads.get())
+            .unwrap_or(1)
+    });
+    match run_bench(&game_code, games, threads) {
+        Ok(report) => print!("{}", format_bench_report(&report)),
+        Err(e) => println!("{}", e),
     }
-    let (width, height) = size.unwrap();
+}
 
-    let theme = get_theme(&args.theme);
-    if theme == None {
-        println!("not found theme {}\r", &args.theme);
+// external players send commands and read the board as json lines, without raw mode or drawing
+fn run_protocol(args: &Args, protocol: &str) -> Result<()> {
+    if protocol != "json" {
+        println!("not found protocol {}, only json is supported", protocol);
         return Ok(());
     }
-    let theme = theme.unwrap();
+    let game_code = match game_code_from_args(args) {
+        Some(game_code) => game_code,
+        None => return Ok(()),
+    };
+    let mut session = match init_session(&game_code) {
+        Ok(session) => session,
+        Err(e) => {
+            println!("{}", e);
+            return Ok(());
+        }
+    };
+    serve(&mut session, stdin().lock(), stdout().lock())
+}
 
-    // terminal setup
+fn setup_terminal(stdout: &mut Stdout) -> Result<bool> {
     enable_raw_mode()?;
-    let mut stdout = stdout();
     let supports_keyboard_enhancement = matches!(
         crossterm::terminal::supports_keyboard_enhancement(),
         Ok(true)
@@ -157,20 +745,195 @@ fn main() -> Result<()> {
             )
         )?;
     }
-    execute!(stdout, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    Ok(supports_keyboard_enhancement)
+}
+
+fn restore_terminal(stdout: &mut Stdout, supports_keyboard_enhancement: bool) -> Result<()> {
+    if supports_keyboard_enhancement {
+        queue!(stdout, PopKeyboardEnhancementFlags)?;
+    }
+    execute!(
+        stdout,
+        PopKeyboardEnhancementFlags,
+        DisableMouseCapture,
+        LeaveAlternateScreen
+    )?;
+    disable_raw_mode()
+}
+
+fn run_replay(path: &str, speed: f64, theme_name: &str) -> Result<()> {
+    let replay = match read_replay(path) {
+        Ok(replay) => replay,
+        Err(e) => {
+            println!("{}", e);
+            return Ok(());
+        }
+    };
+    if let Err(e) = check_replay(&replay) {
+        println!("{}", e);
+    }
+    let playback = match init_playback(replay, speed) {
+        Ok(playback) => playback,
+        Err(e) => {
+            println!("{}", e);
+            return Ok(());
+        }
+    };
+    let theme = match load_theme(theme_name) {
+        Ok(theme) => theme,
+        Err(e) => {
+            println!("{}", e);
+            return Ok(());
+        }
+    };
+    let mut game_board = init_board(playback.game.clone(), theme);
+
+    let mut stdout = stdout();
+    let supports_keyboard_enhancement = setup_terminal(&mut stdout)?;
+    if let Ok((columns, rows)) = crossterm::terminal::size() {
+        game_board.resize(rows as usize, columns as usize);
+    }
+    if let Err(e) = playback_loop(playback, &mut game_board, &stdout) {
+        println!("{}\r", e);
+    }
+    restore_terminal(&mut stdout, supports_keyboard_enhancement)
+}
+
+fn main() -> Result<()> {
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    // the options of the command line take precedence over the config file
+    let path = match &args.config {
+        Some(path) => Some(PathBuf::from(path)),
+        None => config_path(),
+    };
+    let mut config = match &path {
+        Some(path) => match read_config(path, args.config.is_some()) {
+            Ok(config) => config,
+            Err(e) => {
+                println!("{}", e);
+                return Ok(());
+            }
+        },
+        None => Config::default(),
+    };
+    // the keys of some actions, on top of the preset
+    let custom_keys = config.key_bindings.take().unwrap_or_default();
+    let mut settings = apply_config(&mut args, &matches, config);
+    settings.push(Setting {
+        name: "key_bindings",
+        value: Some(format_key_bindings(&custom_keys)).filter(|_| !custom_keys.is_empty()),
+        source: if custom_keys.is_empty() {
+            Source::Default
+        } else {
+            Source::ConfigFile
+        },
+    });
+
+    match &args.command {
+        Some(Command::Config {
+            action: ConfigAction::Show,
+        }) => {
+            print!("{}", format_settings(path.as_deref(), &settings));
+            return Ok(());
+        }
+        Some(Command::Stats) => {
+            print_stats();
+            return Ok(());
+        }
+        Some(Command::Replay { file, speed }) => return run_replay(file, *speed, &args.theme),
+        Some(Command::Bench {
+            size,
+            mines,
+            games,
+            threads,
+            first_click,
+            seed,
+        }) => {
+            run_bench_command(size, *mines, *games, *threads, first_click, *seed);
+            return Ok(());
+        }
+        None => (),
+    }
+    if args.list_themes {
+        print_themes();
+        return Ok(());
+    }
+    if let Some(protocol) = &args.protocol {
+        return run_protocol(&args, protocol);
+    }
 
     // board setup
-    let game_board = init_random_game((height, width), args.bomb_percentage, theme);
+    let game_board = board_from_args(&args);
+    if game_board.is_none() {
+        return Ok(());
+    }
+    let mut game_board = game_board.unwrap();
+    if let Some(palette_name) = &args.palette {
+        match get_palette(palette_name) {
+            Some(number_colors) => game_board.theme.number_colors = number_colors,
+            None => {
+                println!("not found palette {}\r", palette_name);
+                return Ok(());
+            }
+        }
+    }
+    let mut key_bindings = match get_key_preset(&args.keys) {
+        Some(key_bindings) => key_bindings,
+        None => {
+            println!(
+                "not found key preset {}, use default, vim or wasd\r",
+                args.keys
+            );
+            return Ok(());
+        }
+    };
+    if let Err(e) = key_bindings.rebind(&custom_keys) {
+        println!("{}\r", e);
+        return Ok(());
+    }
+    game_board.key_bindings = key_bindings;
+    match get_chord_mode(&args.chord) {
+        Some(chord_mode) => game_board.chord_mode = chord_mode,
+        None => {
+            println!(
+                "not found chord mode {}, use classic or smart\r",
+                args.chord
+            );
+            return Ok(());
+        }
+    }
+    // a resumed game is saved again to its own file
+    let save_path = match &args.resume {
+        Some(path) => path.clone(),
+        None => save_path().map_or(FALLBACK_SAVE_PATH.to_owned(), |path| {
+            path.display().to_string()
+        }),
+    };
+
+    // terminal setup
+    let mut stdout = stdout();
+    let supports_keyboard_enhancement = setup_terminal(&mut stdout)?;
+    if let Ok((columns, rows)) = crossterm::terminal::size() {
+        game_board.resize(rows as usize, columns as usize);
+    }
 
     // event_loop
-    if let Err(e) = event_loop(game_board, &stdout) {
-        println!("{}\r", e.to_string());
+    if let Err(e) = event_loop(&mut game_board, &stdout, &save_path) {
+        println!("{}\r", e);
+    }
+    if let Some(record_path) = &args.record {
+        match write_replay(record_path, &game_board.to_replay()) {
+            Ok(()) => println!(
+                "replay saved, watch it with: minesweeper replay {}\r",
+                record_path
+            ),
+            Err(e) => println!("{}\r", e),
+        }
     }
 
     // terminal exit
-    if supports_keyboard_enhancement {
-        queue!(stdout, PopKeyboardEnhancementFlags)?;
-    }
-    execute!(stdout, PopKeyboardEnhancementFlags, DisableMouseCapture)?;
-    disable_raw_mode()
+    restore_terminal(&mut stdout, supports_keyboard_enhancement)
 }
@@ -0,0 +1,184 @@
+use std::io::{Error, ErrorKind, Result, Stdout};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{poll, read, Event, KeyCode, KeyEventKind, KeyModifiers, MouseEventKind};
+
+use minesweeper::game::Game;
+use minesweeper::history::History;
+use minesweeper::replay::{play, play_move, start_game, Replay};
+
+use crate::board::Board;
+
+const FASTEST_SPEED: f64 = 64.0;
+const SLOWEST_SPEED: f64 = 0.125;
+
+// a replay on its way from the first to the last move
+pub struct Playback {
+    replay: Replay,
+    // the number of moves played
+    position: usize,
+    pub game: Game,
+    history: History,
+    speed: f64,
+    paused: bool,
+}
+
+pub fn init_playback(replay: Replay, speed: f64) -> std::result::Result<Playback, String> {
+    if !(SLOWEST_SPEED..=FASTEST_SPEED).contains(&speed) {
+        return Err(format!(
+            "wrong speed {}, enter a number between {} and {}",
+            speed, SLOWEST_SPEED, FASTEST_SPEED
+        ));
+    }
+    // a broken move is found before the playback starts
+    play(&replay, replay.moves.len())?;
+    Ok(Playback {
+        game: start_game(&replay)?,
+        replay,
+        position: 0,
+        history: History::default(),
+        speed,
+        paused: false,
+    })
+}
+
+fn to_io_error(e: String) -> Error {
+    Error::new(ErrorKind::InvalidData, e)
+}
+
+impl Playback {
+    fn step_forward(&mut self) -> Result<()> {
+        if let Some(recorded_move) = self.replay.moves.get(self.position) {
+            play_move(&mut self.game, &mut self.history, recorded_move).map_err(to_io_error)?;
+            self.position += 1;
+        }
+        Ok(())
+    }
+
+    // the moves are played again from the start, the history only goes one way
+    fn jump_to(&mut self, position: usize) -> Result<()> {
+        self.game = start_game(&self.replay).map_err(to_io_error)?;
+        self.history = History::default();
+        self.position = 0;
+        while self.position < position.min(self.replay.moves.len()) {
+            self.step_forward()?;
+        }
+        Ok(())
+    }
+
+    fn time_until_next_move(&self) -> Option<Duration> {
+        if self.paused {
+            return None;
+        }
+        let next_move = self.replay.moves.get(self.position)?;
+        let previous_milliseconds = match self.position {
+            0 => 0,
+            position => self.replay.moves[position - 1].milliseconds,
+        };
+        let milliseconds = next_move.milliseconds.saturating_sub(previous_milliseconds);
+        Some(Duration::from_secs_f64(
+            milliseconds as f64 / 1000.0 / self.speed,
+        ))
+    }
+
+    fn show(&self, game_board: &mut Board) {
+        let last_move = match self.position {
+            0 => None,
+            position => self.replay.moves.get(position - 1),
+        };
+        game_board.show_game(
+            self.game.clone(),
+            last_move.and_then(|last_move| last_move.cell),
+            Duration::from_millis(last_move.map_or(0, |last_move| last_move.milliseconds)),
+        );
+        let state = if self.position == self.replay.moves.len() {
+            "end"
+        } else if self.paused {
+            "paused"
+        } else {
+            "playing"
+        };
+        game_board.footer = Some(format!(
+            "replay move {}/{}  speed {}x  {}  space: pause  left/right: step  home/end: jump  +/-: speed  q: quit",
+            self.position,
+            self.replay.moves.len(),
+            self.speed,
+            state
+        ));
+    }
+}
+
+pub fn playback_loop(
+    mut playback: Playback,
+    game_board: &mut Board,
+    stdout: &Stdout,
+) -> Result<()> {
+    let mut changed = true;
+    let mut next_move_at: Option<Instant> = None;
+    loop {
+        if changed {
+            playback.show(game_board);
+            next_move_at = playback
+                .time_until_next_move()
+                .map(|timeout| Instant::now() + timeout);
+            changed = false;
+        }
+        game_board.draw(stdout)?;
+
+        // wait for the input, but wake up for the next move
+        let event = match next_move_at {
+            Some(next_move_at) => {
+                if poll(next_move_at.saturating_duration_since(Instant::now()))? {
+                    Some(read()?)
+                } else {
+                    None
+                }
+            }
+            None => Some(read()?),
+        };
+        match event {
+            None => {
+                playback.step_forward()?;
+                changed = true;
+            }
+            Some(Event::Key(key_event)) if key_event.kind == KeyEventKind::Press => {
+                changed = true;
+                match key_event.code {
+                    KeyCode::Char('c') if key_event.modifiers == KeyModifiers::CONTROL => {
+                        return Ok(())
+                    }
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char(' ') | KeyCode::Char('p') => playback.paused = !playback.paused,
+                    KeyCode::Right | KeyCode::Char('.') => {
+                        playback.paused = true;
+                        playback.step_forward()?;
+                    }
+                    KeyCode::Left | KeyCode::Char(',') => {
+                        playback.paused = true;
+                        playback.jump_to(playback.position.saturating_sub(1))?;
+                    }
+                    KeyCode::Home => playback.jump_to(0)?,
+                    KeyCode::End => playback.jump_to(playback.replay.moves.len())?,
+                    KeyCode::Char('+') | KeyCode::Char('=') => {
+                        playback.speed = (playback.speed * 2.0).min(FASTEST_SPEED)
+                    }
+                    KeyCode::Char('-') => {
+                        playback.speed = (playback.speed / 2.0).max(SLOWEST_SPEED)
+                    }
+                    _ => changed = false,
+                }
+            }
+            Some(Event::Mouse(mouse_event)) => match mouse_event.kind {
+                MouseEventKind::ScrollUp => game_board.pan(-1, 0),
+                MouseEventKind::ScrollDown => game_board.pan(1, 0),
+                MouseEventKind::ScrollLeft => game_board.pan(0, -1),
+                MouseEventKind::ScrollRight => game_board.pan(0, 1),
+                _ => {}
+            },
+            Some(Event::Resize(columns, rows)) => {
+                game_board.resize(rows as usize, columns as usize)
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use std::io::{self, BufRead, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{check_game_code, init_game_from_code, Changes, Game, GameStatus};
+use crate::game_code::{topology_name, GameCode};
+
+// one command of an external player, read as a line of json like {"command":"reveal","row":2,"column":5}
+#[derive(Deserialize, PartialEq, Debug)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+    Reveal {
+        row: usize,
+        column: usize,
+    },
+    // adds one flag, a cell can hold as many flags as it can hold mines
+    Flag {
+        row: usize,
+        column: usize,
+    },
+    // takes all the flags of the cell away
+    Unflag {
+        row: usize,
+        column: usize,
+    },
+    Chord {
+        row: usize,
+        column: usize,
+    },
+    State,
+    // without a code the next game has the same options and a new seed
+    New {
+        #[serde(default)]
+        code: Option<String>,
+    },
+}
+
+// the cells changed by a move
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct MoveResult {
+    pub discovered: Vec<(usize, usize)>,
+    pub flagged: Vec<(usize, usize)>,
+    pub unflagged: Vec<(usize, usize)>,
+}
+
+// what a player can see of the game, every row of the board is a string with one character per cell:
+// '.' unknown, 'F' flagged, '0' to '9' discovered numbers and '#' for the larger ones;
+// when the game is over also '*' bomb and 'X' the discovered bomb
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct State {
+    pub width: usize,
+    pub height: usize,
+    pub mines: usize,
+    pub remaining_flags: usize,
+    // square or hex, the odd rows of a hex board are shifted right by half a cell
+    pub topology: String,
+    // the edges touch the opposite edges
+    pub wrap: bool,
+    // the numbers count the mines, and a cell can hold up to this many of them
+    pub max_mines_per_cell: usize,
+    // ongoing, won or lost
+    pub status: String,
+    // the game code has the seed, so it is only given when the game is over
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub board: Vec<String>,
+    // the number of flags in every cell, only when a cell can hold several mines
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flags: Option<Vec<String>>,
+    // the discovered numbers as integers, only when a number can go above 9
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub numbers: Option<Vec<Vec<Option<usize>>>>,
+}
+
+// the answer to every request, written as one line of json
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<MoveResult>,
+    pub state: State,
+}
+
+pub struct Session {
+    pub game: Game,
+    // the options of the games started with new
+    game_code: GameCode,
+}
+
+pub fn init_session(game_code: &GameCode) -> Result<Session, String> {
+    check_game_code(game_code)?;
+    Ok(Session {
+        game: init_game_from_code(game_code)?,
+        game_code: game_code.clone(),
+    })
+}
+
+fn format_state(game: &Game) -> State {
+    let is_over = game.status != GameStatus::OnGoing;
+    let board = game
+        .cells
+        .iter()
+        .map(|cells| {
+            cells
+                .iter()
+                .map(|cell| {
+                    if cell.is_discovered && cell.is_bomb {
+                        'X'
+                    } else if cell.is_discovered {
+                        u32::try_from(cell.number_of_adjusted_bombs)
+                            .ok()
+                            .and_then(|number| char::from_digit(number, 10))
+                            .unwrap_or('#')
+                    } else if cell.is_flagged {
+                        'F'
+                    } else if is_over && cell.is_bomb {
+                        '*'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    let flags = (game.game_code.max_bombs_per_cell > 1).then(|| {
+        game.cells
+            .iter()
+            .map(|cells| {
+                cells
+                    .iter()
+                    .map(|cell| char::from_digit(cell.number_of_flags as u32, 10).unwrap_or('?'))
+                    .collect()
+            })
+            .collect()
+    });
+    let numbers = (game.max_number() > 9).then(|| {
+        game.cells
+            .iter()
+            .map(|cells| {
+                cells
+                    .iter()
+                    .map(|cell| {
+                        (cell.is_discovered && !cell.is_bomb)
+                            .then_some(cell.number_of_adjusted_bombs)
+                    })
+                    .collect()
+            })
+            .collect()
+    });
+
+    State {
+        width: game.size.1,
+        height: game.size.0,
+        mines: game.number_of_bombs,
+        remaining_flags: game.remaining_flags,
+        topology: topology_name(game.game_code.topology).to_owned(),
+        wrap: game.game_code.wrap,
+        max_mines_per_cell: game.game_code.max_bombs_per_cell,
+        status: match game.status {
+            GameStatus::OnGoing => "ongoing",
+            GameStatus::Won => "won",
+            GameStatus::Lost(_, _) => "lost",
+        }
+        .to_owned(),
+        code: is_over.then(|| game.game_code.to_string()),
+        board,
+        flags,
+        numbers,
+    }
+}
+
+impl Session {
+    pub fn handle_line(&mut self, line: &str) -> Response {
+        let result = serde_json::from_str::<Request>(line)
+            .map_err(|e| format!("wrong request {}: {}", line.trim(), e))
+            .and_then(|request| self.handle_request(request));
+        let (error, result) = match result {
+            Ok(result) => (None, result),
+            Err(e) => (Some(e), None),
+        };
+        Response {
+            ok: error.is_none(),
+            error,
+            result,
+            state: format_state(&self.game),
+        }
+    }
+
+    fn handle_request(&mut self, request: Request) -> Result<Option<MoveResult>, String> {
+        let changes = match request {
+            Request::State => return Ok(None),
+            Request::New { code } => {
+                let game_code = match code {
+                    Some(code) => code.parse::<GameCode>()?,
+                    None => GameCode {
+                        seed: rand::random(),
+                        ..self.game_code.clone()
+                    },
+                };
+                *self = init_session(&game_code)?;
+                return Ok(None);
+            }
+            Request::Reveal { row, column } => {
+                let cell = self.check_move(row, column)?;
+                self.game.reveal(cell)
+            }
+            Request::Flag { row, column } => {
+                let cell = self.check_move(row, column)?;
+                self.game.set_flag(cell, true)
+            }
+            Request::Unflag { row, column } => {
+                let cell = self.check_move(row, column)?;
+                self.game.set_flag(cell, false)
+            }
+            Request::Chord { row, column } => {
+                let cell = self.check_move(row, column)?;
+                self.game.chord(cell)
+            }
+        };
+        Ok(Some(move_result(changes)))
+    }
+
+    fn check_move(&self, row: usize, column: usize) -> Result<(usize, usize), String> {
+        if self.game.status != GameStatus::OnGoing {
+            return Err("the game is over, start another one with new".to_owned());
+        }
+        if row >= self.game.size.0 || column >= self.game.size.1 {
+            return Err(format!(
+                "row {} column {} is outside the {}x{} board",
+                row, column, self.game.size.1, self.game.size.0
+            ));
+        }
+        Ok((row, column))
+    }
+}
+
+fn move_result(changes: Changes) -> MoveResult {
+    MoveResult {
+        discovered: changes.discovered_cells,
+        flagged: changes.flagged_cells,
+        unflagged: changes.unflagged_cells,
+    }
+}
+
+// answers every line of the input with a line of the output, until the input ends
+pub fn serve(session: &mut Session, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = session.handle_line(&line);
+        let json = serde_json::to_string(&response).map_err(io::Error::other)?;
+        writeln!(output, "{}", json)?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{init_game_from_state, FirstClickRule, Neighbourhood, Topology};
+
+    fn test_session() -> Session {
+        init_session(&GameCode {
+            size: (4, 5),
+            number_of_bombs: 3,
+            first_click_rule: FirstClickRule::Zero,
+            no_guess: false,
+            topology: Topology::Square,
+            neighbourhood: Neighbourhood::Standard,
+            max_bombs_per_cell: 1,
+            wrap: false,
+            seed: 7,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn state_hides_the_bombs_until_the_game_ends() {
+        let mut session = test_session();
+        let response = session.handle_line(r#"{"command":"reveal","row":0,"column":0}"#);
+        assert!(response.ok);
+        assert!(!response.result.unwrap().discovered.is_empty());
+        assert_eq!(response.state.status, "ongoing");
+        assert_eq!(response.state.code, None);
+        assert_eq!(response.state.board.len(), 4);
+        assert!(response.state.board.iter().all(|row| row.len() == 5));
+        assert!(response.state.board.iter().all(|row| !row.contains('*')));
+
+        // step on a bomb
+        let game = &session.game;
+        let bomb = (0..4)
+            .flat_map(|row| (0..5).map(move |column| (row, column)))
+            .find(|&(row, column)| game.cells[row][column].is_bomb)
+            .unwrap();
+        let response = session.handle_line(&format!(
+            r#"{{"command":"reveal","row":{},"column":{}}}"#,
+            bomb.0, bomb.1
+        ));
+        assert_eq!(response.state.status, "lost");
+        assert_eq!(response.state.code, Some("5x4-3-zero-7".to_owned()));
+        assert_eq!(response.state.board[bomb.0].chars().nth(bomb.1), Some('X'));
+        assert!(response.state.board.concat().contains('*'));
+    }
+
+    #[test]
+    fn large_numbers_are_sent_as_integers() {
+        let game_code = GameCode {
+            size: (7, 7),
+            number_of_bombs: 48,
+            first_click_rule: FirstClickRule::Off,
+            no_guess: false,
+            topology: Topology::Square,
+            neighbourhood: Neighbourhood::Radius(3),
+            max_bombs_per_cell: 1,
+            wrap: false,
+            seed: 7,
+        };
+        let mut session = init_session(&game_code).unwrap();
+        let bombs: Vec<(usize, usize)> = (0..7)
+            .flat_map(|row| (0..7).map(move |column| (row, column)))
+            .filter(|&cell| cell != (3, 3))
+            .collect();
+        session.game = init_game_from_state(game_code, Some(&bombs), &[], &[]);
+        let response = session.handle_line(r#"{"command":"reveal","row":3,"column":3}"#);
+        assert_eq!(response.state.board[3], "***#***");
+        let numbers = response.state.numbers.unwrap();
+        assert_eq!(numbers[3][3], Some(48));
+        assert_eq!(numbers[0][0], None);
+        assert_eq!(
+            test_session()
+                .handle_line(r#"{"command":"state"}"#)
+                .state
+                .numbers,
+            None
+        );
+    }
+
+    #[test]
+    fn wrong_requests_keep_the_game() {
+        let mut session = test_session();
+        for line in [
+            r#"{"command":"reveal","row":4,"column":0}"#,
+            r#"{"command":"dig"}"#,
+            "reveal 0 0",
+        ] {
+            let response = session.handle_line(line);
+            assert!(!response.ok);
+            assert!(response.error.is_some());
+            assert_eq!(response.result, None);
+            assert!(response.state.board.iter().all(|row| row == "....."));
+        }
+    }
+
+    #[test]
+    fn serve_answers_every_line() {
+        let mut session = test_session();
+        let input = concat!(
+            r#"{"command":"state"}"#,
+            "\n\n",
+            r#"{"command":"flag","row":3,"column":4}"#,
+            "\n",
+            r#"{"command":"new","code":"9x9-10-safe-2a"}"#,
+            "\n"
+        );
+        let mut output: Vec<u8> = Vec::new();
+        serve(&mut session, input.as_bytes(), &mut output).unwrap();
+        let responses: Vec<Response> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[1].result.as_ref().unwrap().flagged, vec![(3, 4)]);
+        assert_eq!(responses[1].state.remaining_flags, 2);
+        assert_eq!(responses[2].state.width, 9);
+        assert_eq!(responses[2].state.mines, 10);
+    }
+}
@@ -0,0 +1,186 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{init_game_from_code, Game};
+use crate::game_code::GameCode;
+use crate::history::History;
+use crate::save::{load_game, save_game, SavedGame};
+
+pub const REPLAY_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Reveal,
+    Flag,
+    Unflag,
+    ToggleFlag,
+    Chord,
+    ClassicChord,
+    Hint,
+    Undo,
+    Redo,
+}
+
+// one action of the player, at the time on the game clock
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Move {
+    pub action: Action,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cell: Option<(usize, usize)>,
+    pub milliseconds: u64,
+}
+
+// the moves of a game from its start, the start is generated again from the game code
+// or loaded from the saved game it was resumed from
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct Replay {
+    pub version: u32,
+    pub game_code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resumed_from: Option<SavedGame>,
+    pub moves: Vec<Move>,
+    // the cells at the end, in the saved game format, to check the playback
+    pub final_cells: Vec<String>,
+}
+
+// returns true if the game has changed, the changed games are kept in the history
+pub fn perform(
+    game: &mut Game,
+    history: &mut History,
+    action: Action,
+    cell: Option<(usize, usize)>,
+) -> bool {
+    let game_before = game.clone();
+    let changes = match (action, cell) {
+        (Action::Undo, _) => return history.undo(game),
+        (Action::Redo, _) => return history.redo(game),
+        (Action::Hint, _) => game.hint(),
+        (Action::Reveal, Some(cell)) => game.reveal(cell),
+        (Action::Flag, Some(cell)) => game.set_flag(cell, true),
+        (Action::Unflag, Some(cell)) => game.set_flag(cell, false),
+        (Action::ToggleFlag, Some(cell)) => game.toggle_flag(cell),
+        (Action::Chord, Some(cell)) => game.chord(cell),
+        (Action::ClassicChord, Some(cell)) => game.classic_chord(cell),
+        (_, None) => return false,
+    };
+    if changes.is_empty() {
+        return false;
+    }
+    history.record(game_before);
+    true
+}
+
+pub fn start_game(replay: &Replay) -> Result<Game, String> {
+    if replay.version != REPLAY_VERSION {
+        return Err(format!(
+            "the replay has version {}, only version {} is supported",
+            replay.version, REPLAY_VERSION
+        ));
+    }
+    match &replay.resumed_from {
+        Some(saved_game) => load_game(saved_game),
+        None => init_game_from_code(&replay.game_code.parse::<GameCode>()?),
+    }
+}
+
+pub fn play_move(
+    game: &mut Game,
+    history: &mut History,
+    recorded_move: &Move,
+) -> Result<(), String> {
+    if let Some((row, column)) = recorded_move.cell {
+        if row >= game.size.0 || column >= game.size.1 {
+            return Err(format!(
+                "the replay has a move on row {} column {}, outside of its {}x{} board",
+                row + 1,
+                column + 1,
+                game.size.1,
+                game.size.0
+            ));
+        }
+    }
+    perform(game, history, recorded_move.action, recorded_move.cell);
+    Ok(())
+}
+
+// the game after some of the moves
+pub fn play(replay: &Replay, number_of_moves: usize) -> Result<Game, String> {
+    let mut game = start_game(replay)?;
+    let mut history = History::default();
+    for recorded_move in replay.moves.iter().take(number_of_moves) {
+        play_move(&mut game, &mut history, recorded_move)?;
+    }
+    Ok(game)
+}
+
+pub fn final_cells(game: &Game) -> Vec<String> {
+    save_game(game, "", std::time::Duration::ZERO).cells
+}
+
+// plays all the moves and compares the end with the recorded one
+pub fn check_replay(replay: &Replay) -> Result<(), String> {
+    let game = play(replay, replay.moves.len())?;
+    if final_cells(&game) != replay.final_cells {
+        return Err(
+            "the replay ends on a different board, it may be from another version".to_owned(),
+        );
+    }
+    Ok(())
+}
+
+pub fn write_replay(path: &str, replay: &Replay) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(replay).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(|e| format!("could not write {}: {}", path, e))
+}
+
+pub fn read_replay(path: &str) -> Result<Replay, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path, e))?;
+    serde_json::from_str(&content).map_err(|e| format!("{} is not a replay: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::GameStatus;
+
+    use super::*;
+
+    // recorded games, played again on every change of the rules
+    const FIXTURES: [&str; 2] = [
+        include_str!("../tests/fixtures/won-no-guess.json"),
+        include_str!("../tests/fixtures/won-after-undo.json"),
+    ];
+
+    #[test]
+    fn fixtures_end_on_their_recorded_board() {
+        for fixture in FIXTURES {
+            let replay: Replay = serde_json::from_str(fixture).unwrap();
+            assert_eq!(check_replay(&replay), Ok(()));
+            assert_eq!(
+                play(&replay, replay.moves.len()).unwrap().status,
+                GameStatus::Won
+            );
+        }
+    }
+
+    #[test]
+    fn undone_losing_reveal_is_replayed() {
+        let replay: Replay = serde_json::from_str(FIXTURES[1]).unwrap();
+        let (lost_at, _) = replay
+            .moves
+            .iter()
+            .enumerate()
+            .find(|(_, recorded_move)| recorded_move.action == Action::Undo)
+            .unwrap();
+
+        assert!(matches!(
+            play(&replay, lost_at).unwrap().status,
+            GameStatus::Lost(_, _)
+        ));
+        let game = play(&replay, lost_at + 1).unwrap();
+        assert_eq!(game.status, GameStatus::OnGoing);
+        assert_eq!(game.undos_used, 1);
+    }
+}
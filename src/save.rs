@@ -0,0 +1,358 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{check_game_code, init_game_from_state, Cell, Game, GameStatus};
+use crate::game_code::GameCode;
+
+pub const SAVE_VERSION: u32 = 1;
+
+// a game in the middle of play, every row of cells is a string with one character per cell:
+// '.' unknown, '*' unknown bomb, 'f' flagged, 'F' flagged bomb, 'o' discovered, 'X' discovered bomb
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SavedGame {
+    pub version: u32,
+    pub game_code: String,
+    pub bombs_placed: bool,
+    pub cells: Vec<String>,
+    pub remaining_flags: usize,
+    pub theme: String,
+    pub elapsed_milliseconds: u64,
+    #[serde(default)]
+    pub hints_used: usize,
+    #[serde(default)]
+    pub undos_used: usize,
+    // when a cell can hold several bombs, the number of bombs and flags with one digit per cell
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bomb_counts: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flag_counts: Vec<String>,
+    // how far the random choices of the hints have gone
+    #[serde(default)]
+    pub random_position: u128,
+}
+
+pub fn save_game(game: &Game, theme: &str, elapsed: Duration) -> SavedGame {
+    let cells = game
+        .cells
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(
+                    |cell| match (cell.is_discovered, cell.is_flagged, cell.is_bomb) {
+                        (true, _, true) => 'X',
+                        (true, _, false) => 'o',
+                        (false, true, true) => 'F',
+                        (false, true, false) => 'f',
+                        (false, false, true) => '*',
+                        (false, false, false) => '.',
+                    },
+                )
+                .collect()
+        })
+        .collect();
+    let format_counts = |count: fn(&Cell) -> u8| -> Vec<String> {
+        if game.game_code.max_bombs_per_cell == 1 {
+            return Vec::new();
+        }
+        game.cells
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| char::from_digit(count(cell) as u32, 10).unwrap_or('?'))
+                    .collect()
+            })
+            .collect()
+    };
+
+    SavedGame {
+        version: SAVE_VERSION,
+        game_code: game.game_code.to_string(),
+        bombs_placed: game.bombs_placed(),
+        cells,
+        remaining_flags: game.remaining_flags,
+        theme: theme.to_owned(),
+        elapsed_milliseconds: elapsed.as_millis() as u64,
+        hints_used: game.hints_used,
+        undos_used: game.undos_used,
+        bomb_counts: format_counts(|cell| cell.number_of_bombs),
+        flag_counts: format_counts(|cell| cell.number_of_flags),
+        random_position: game.random_position(),
+    }
+}
+
+// one digit per cell, or None when the counts are left out
+fn parse_counts(
+    lines: &[String],
+    name: &str,
+    (height, width): (usize, usize),
+) -> Result<Option<Vec<Vec<usize>>>, String> {
+    if lines.is_empty() {
+        return Ok(None);
+    }
+    let wrong_counts = || {
+        format!(
+            "the {} counts of the saved game need {} rows of {} digits",
+            name, height, width
+        )
+    };
+    if lines.len() != height {
+        return Err(wrong_counts());
+    }
+    let mut counts = Vec::new();
+    for line in lines {
+        let row = line
+            .chars()
+            .map(|digit| digit.to_digit(10).map(|count| count as usize))
+            .collect::<Option<Vec<usize>>>()
+            .ok_or_else(wrong_counts)?;
+        if row.len() != width {
+            return Err(wrong_counts());
+        }
+        counts.push(row);
+    }
+    Ok(Some(counts))
+}
+
+pub fn load_game(saved_game: &SavedGame) -> Result<Game, String> {
+    if saved_game.version != SAVE_VERSION {
+        return Err(format!(
+            "the saved game has version {}, only version {} is supported",
+            saved_game.version, SAVE_VERSION
+        ));
+    }
+    let game_code = saved_game.game_code.parse::<GameCode>()?;
+    check_game_code(&game_code)?;
+    let (height, width) = game_code.size;
+    if saved_game.cells.len() != height {
+        return Err(format!(
+            "the saved game has {} rows of cells, but its {}x{} board needs {}",
+            saved_game.cells.len(),
+            width,
+            height,
+            height
+        ));
+    }
+
+    let bomb_counts = parse_counts(&saved_game.bomb_counts, "bomb", (height, width))?;
+    let flag_counts = parse_counts(&saved_game.flag_counts, "flag", (height, width))?;
+    // a cell with several bombs or flags is listed once for each of them
+    let count = |counts: &Option<Vec<Vec<usize>>>, (row, column): (usize, usize), is_set: bool| {
+        counts
+            .as_ref()
+            .map_or(usize::from(is_set), |counts| counts[row][column])
+    };
+
+    let mut bomb_cells: Vec<(usize, usize)> = Vec::new();
+    let mut discovered_cells: Vec<(usize, usize)> = Vec::new();
+    let mut flagged_cells: Vec<(usize, usize)> = Vec::new();
+    for (row, line) in saved_game.cells.iter().enumerate() {
+        if line.chars().count() != width {
+            return Err(format!(
+                "row {} of the saved game has {} cells, but its {}x{} board needs {}",
+                row + 1,
+                line.chars().count(),
+                width,
+                height,
+                width
+            ));
+        }
+        for (column, symbol) in line.chars().enumerate() {
+            let (is_discovered, is_flagged, is_bomb) = match symbol {
+                '.' => (false, false, false),
+                '*' => (false, false, true),
+                'f' => (false, true, false),
+                'F' => (false, true, true),
+                'o' => (true, false, false),
+                'X' => (true, false, true),
+                _ => {
+                    return Err(format!(
+                        "row {} column {} of the saved game has an unknown cell '{}'",
+                        row + 1,
+                        column + 1,
+                        symbol
+                    ))
+                }
+            };
+            let bombs = count(&bomb_counts, (row, column), is_bomb);
+            let flags = count(&flag_counts, (row, column), is_flagged);
+            let max = game_code.max_bombs_per_cell;
+            if is_bomb != (bombs > 0) || is_flagged != (flags > 0) || bombs > max || flags > max {
+                return Err(format!(
+                    "row {} column {} of the saved game has {} bombs and {} flags in a cell '{}'",
+                    row + 1,
+                    column + 1,
+                    bombs,
+                    flags,
+                    symbol
+                ));
+            }
+            if is_discovered {
+                discovered_cells.push((row, column));
+            }
+            bomb_cells.extend(std::iter::repeat_n((row, column), bombs));
+            flagged_cells.extend(std::iter::repeat_n((row, column), flags));
+        }
+    }
+
+    // a crowded board may have fewer bombs than its game code asks for
+    if bomb_cells.len() > game_code.number_of_bombs {
+        return Err(format!(
+            "the saved game has {} bombs, but its game code says {}",
+            bomb_cells.len(),
+            game_code.number_of_bombs
+        ));
+    }
+    if !saved_game.bombs_placed && (!bomb_cells.is_empty() || !discovered_cells.is_empty()) {
+        return Err(
+            "the saved game has bombs or discovered cells before its bombs are placed".to_owned(),
+        );
+    }
+    let number_of_bombs = if saved_game.bombs_placed {
+        bomb_cells.len()
+    } else {
+        game_code.number_of_bombs
+    };
+    if flagged_cells.len() + saved_game.remaining_flags != number_of_bombs {
+        return Err(format!(
+            "the saved game has {} flags and {} remaining flags, but {} bombs",
+            flagged_cells.len(),
+            saved_game.remaining_flags,
+            number_of_bombs
+        ));
+    }
+
+    let bomb_cells = if saved_game.bombs_placed {
+        Some(bomb_cells.as_slice())
+    } else {
+        None
+    };
+    let mut game = init_game_from_state(game_code, bomb_cells, &discovered_cells, &flagged_cells);
+    if game.status != GameStatus::OnGoing {
+        return Err("the saved game is already over".to_owned());
+    }
+    game.hints_used = saved_game.hints_used;
+    game.undos_used = saved_game.undos_used;
+    game.set_random_position(saved_game.random_position);
+    Ok(game)
+}
+
+// the saved game in the data directory, next to the stats, like ~/.local/share/minesweeper/save.json
+pub fn save_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|path| path.join("minesweeper").join("save.json"))
+}
+
+pub fn write_saved_game(path: &str, saved_game: &SavedGame) -> Result<(), String> {
+    let write_error = |e: std::io::Error| format!("could not write {}: {}", path, e);
+    if let Some(directory) = Path::new(path).parent() {
+        fs::create_dir_all(directory).map_err(write_error)?;
+    }
+    let content = serde_json::to_string_pretty(saved_game).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(write_error)
+}
+
+pub fn read_saved_game(path: &str) -> Result<SavedGame, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path, e))?;
+    serde_json::from_str(&content).map_err(|e| format!("{} is not a saved game: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{init_game_from_code, init_random_game, FirstClickRule};
+
+    use super::*;
+
+    #[test]
+    fn save_and_load() {
+        let mut game = init_random_game((8, 12), 19, FirstClickRule::Zero, 5);
+        game.reveal((3, 3));
+        game.toggle_flag((7, 11));
+        let saved_game = save_game(&game, "borderless", Duration::from_secs(12));
+
+        let loaded_game = load_game(&saved_game).unwrap();
+
+        assert_eq!(
+            save_game(&loaded_game, "borderless", Duration::from_secs(12)),
+            saved_game
+        );
+        assert_eq!(loaded_game.remaining_flags, 18);
+        for (row, cells) in game.cells.iter().enumerate() {
+            for (column, cell) in cells.iter().enumerate() {
+                let loaded_cell = &loaded_game.cells[row][column];
+                assert_eq!(
+                    cell.number_of_adjusted_bombs,
+                    loaded_cell.number_of_adjusted_bombs
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn hints_go_on_after_a_load() {
+        let mut game = init_random_game((16, 30), 99, FirstClickRule::Zero, 8);
+        game.reveal((8, 15));
+        game.hint();
+        let mut loaded_game = load_game(&save_game(&game, "border", Duration::ZERO)).unwrap();
+        for _ in 0..5 {
+            assert_eq!(game.hint(), loaded_game.hint());
+        }
+    }
+
+    #[test]
+    fn save_and_load_multi_mine_cells() {
+        let game_code: GameCode = "6x4-12-safe-multi3-9".parse().unwrap();
+        let mut game = init_game_from_code(&game_code).unwrap();
+        game.reveal((0, 0));
+        game.toggle_flag((3, 5));
+        game.toggle_flag((3, 5));
+        let saved_game = save_game(&game, "border", Duration::ZERO);
+        assert_eq!(saved_game.bomb_counts.len(), 4);
+
+        let loaded_game = load_game(&saved_game).unwrap();
+        assert_eq!(loaded_game.cells[3][5].number_of_flags, 2);
+        assert_eq!(
+            save_game(&loaded_game, "border", Duration::ZERO),
+            saved_game
+        );
+
+        let mut wrong_game = saved_game.clone();
+        wrong_game.flag_counts[3].replace_range(5..6, "4");
+        assert!(load_game(&wrong_game).is_err());
+    }
+
+    #[test]
+    fn load_rejects_finished_game() {
+        let mut game = init_random_game((8, 12), 19, FirstClickRule::Off, 5);
+        let bomb = game
+            .cells
+            .iter()
+            .flatten()
+            .position(|cell| cell.is_bomb)
+            .unwrap();
+        game.reveal((bomb / 12, bomb % 12));
+        let saved_game = save_game(&game, "border", Duration::ZERO);
+
+        assert_eq!(
+            load_game(&saved_game).err(),
+            Some("the saved game is already over".to_owned())
+        );
+    }
+
+    #[test]
+    fn load_rejects_wrong_size() {
+        let game = init_random_game((3, 3), 2, FirstClickRule::Safe, 5);
+        let mut saved_game = save_game(&game, "border", Duration::ZERO);
+        saved_game.cells[1].push('.');
+
+        assert_eq!(
+            load_game(&saved_game).err(),
+            Some("row 2 of the saved game has 4 cells, but its 3x3 board needs 3".to_owned())
+        );
+
+        saved_game.cells.pop();
+        assert!(load_game(&saved_game).is_err());
+    }
+}
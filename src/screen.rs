@@ -0,0 +1,127 @@
+use std::io::{Result, Write};
+
+use crossterm::{
+    cursor::MoveTo,
+    queue,
+    style::Print,
+    terminal::{Clear, ClearType},
+};
+
+// a frame is a list of lines, every line has one styled piece per terminal column
+pub type Frame = Vec<Vec<String>>;
+
+// the frame on the terminal, so a redraw only prints the pieces that changed
+pub struct Screen {
+    shown_frame: Frame,
+    need_to_clear: bool,
+}
+
+pub fn init_screen() -> Screen {
+    Screen {
+        shown_frame: Vec::new(),
+        need_to_clear: true,
+    }
+}
+
+impl Screen {
+    // the next render redraws everything, like after a resize
+    pub fn invalidate(&mut self) {
+        self.need_to_clear = true;
+    }
+
+    pub fn render(&mut self, out: &mut impl Write, frame: Frame) -> Result<()> {
+        // empty pieces take no column
+        let frame: Frame = frame
+            .into_iter()
+            .map(|line| line.into_iter().filter(|piece| !piece.is_empty()).collect())
+            .collect();
+
+        if self.need_to_clear {
+            queue!(out, Clear(ClearType::All))?;
+            self.shown_frame = Vec::new();
+            self.need_to_clear = false;
+        }
+
+        for (row, line) in frame.iter().enumerate() {
+            let shown_line = self.shown_frame.get(row);
+            let mut cursor_column: Option<usize> = None;
+            for (column, piece) in line.iter().enumerate() {
+                if shown_line.and_then(|shown_line| shown_line.get(column)) == Some(piece) {
+                    continue;
+                }
+                if cursor_column != Some(column) {
+                    queue!(out, MoveTo(column as u16, row as u16))?;
+                }
+                queue!(out, Print(piece))?;
+                cursor_column = Some(column + 1);
+            }
+            if shown_line.map_or(0, |shown_line| shown_line.len()) > line.len() {
+                queue!(
+                    out,
+                    MoveTo(line.len() as u16, row as u16),
+                    Clear(ClearType::UntilNewLine)
+                )?;
+            }
+        }
+        if self.shown_frame.len() > frame.len() {
+            queue!(
+                out,
+                MoveTo(0, frame.len() as u16),
+                Clear(ClearType::FromCursorDown)
+            )?;
+        }
+
+        // messages go below the frame
+        queue!(out, MoveTo(0, frame.len() as u16))?;
+        out.flush()?;
+        self.shown_frame = frame;
+        Ok(())
+    }
+
+    // prints the last frame as plain lines, to keep it after leaving the alternate screen
+    pub fn print_shown_frame(&self) {
+        for line in &self.shown_frame {
+            println!("{}\r", line.concat());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(lines: &[&str]) -> Frame {
+        lines
+            .iter()
+            .map(|line| line.chars().map(|c| c.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn render_only_changed_pieces() {
+        let mut screen = init_screen();
+        let mut out: Vec<u8> = Vec::new();
+        screen.render(&mut out, frame(&["abc", "def"])).unwrap();
+
+        out.clear();
+        screen.render(&mut out, frame(&["abc", "dXY"])).unwrap();
+        assert_eq!(
+            String::from_utf8(out.clone()).unwrap(),
+            format!("{}XY{}", MoveTo(1, 1), MoveTo(0, 2))
+        );
+
+        out.clear();
+        screen.render(&mut out, frame(&["ab"])).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(
+                "{}{}{}{}{}",
+                MoveTo(2, 0),
+                Clear(ClearType::UntilNewLine),
+                MoveTo(0, 1),
+                Clear(ClearType::FromCursorDown),
+                MoveTo(0, 1)
+            )
+        );
+    }
+}
@@ -0,0 +1,701 @@
+use std::collections::HashMap;
+
+use crate::game::Game;
+
+// components that need more steps to count are skipped, to keep the solver fast,
+// their cells are then guessed like the cells away from the numbers
+const MAX_PLACEMENT_STEPS: usize = 200_000;
+
+// why a cell is proven safe or bomb, the cells are the discovered numbers it comes from
+#[derive(Clone, PartialEq, Debug)]
+pub enum Reason {
+    // all the bombs around the number are flagged
+    NumberIsFlagged((usize, usize)),
+    // the number has as many unknown cells as missing bombs
+    NumberIsFull((usize, usize)),
+    // the unknown cells of the small number are all around the big one too,
+    // which leaves some bombs for the other cells of the big one
+    SharedCells {
+        small: (usize, usize),
+        big: (usize, usize),
+        bombs: usize,
+    },
+    // every way to place the bombs around these numbers agrees
+    AllPlacements(Vec<(usize, usize)>),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Explanation {
+    pub cell: (usize, usize),
+    pub is_bomb: bool,
+    pub reason: Reason,
+}
+
+// cells that can be proven safe or bomb from the discovered numbers and the flags
+pub struct Deduction {
+    pub safe_cells: Vec<(usize, usize)>,
+    pub bomb_cells: Vec<(usize, usize)>,
+    pub explanations: Vec<Explanation>,
+}
+
+// the unknown cells around a discovered number and how many bombs are among them
+struct Constraint {
+    number_cell: (usize, usize),
+    cells: Vec<(usize, usize)>,
+    bombs: usize,
+}
+fn collect_constraints(game: &Game) -> Vec<Constraint> {
+    let mut constraints: Vec<Constraint> = Vec::new();
+    for row in 0..game.size.0 {
+        for column in 0..game.size.1 {
+            let cell = &game.cells[row][column];
+            if !cell.is_discovered || cell.is_bomb {
+                continue;
+            }
+            let mut unknown_cells: Vec<(usize, usize)> = Vec::new();
+            let mut flagged_cells = 0;
+            for index in game.get_adjusted_indices((row, column)) {
+                let adjusted_cell = &game.cells[index.0][index.1];
+                if adjusted_cell.is_flagged {
+                    flagged_cells += 1;
+                } else if !adjusted_cell.is_discovered {
+                    unknown_cells.push(index);
+                }
+            }
+            let number = cell.number_of_adjusted_bombs;
+            // a wrong flag makes the number unusable
+            if unknown_cells.is_empty() || flagged_cells > number {
+                continue;
+            }
+            unknown_cells.sort();
+            constraints.push(Constraint {
+                number_cell: (row, column),
+                cells: unknown_cells,
+                bombs: number - flagged_cells,
+            });
+        }
+    }
+    constraints
+}
+
+fn is_subset(small: &[(usize, usize)], big: &[(usize, usize)]) -> bool {
+    small.len() <= big.len() && small.iter().all(|index| big.binary_search(index).is_ok())
+}
+
+// the constraints that share unknown cells, directly or through other constraints
+fn connected_components(constraints: &[Constraint]) -> Vec<Vec<usize>> {
+    let mut constraints_of_cell: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for (index, constraint) in constraints.iter().enumerate() {
+        for cell in &constraint.cells {
+            constraints_of_cell.entry(*cell).or_default().push(index);
+        }
+    }
+
+    let mut visited = vec![false; constraints.len()];
+    let mut components: Vec<Vec<usize>> = Vec::new();
+    for first in 0..constraints.len() {
+        if visited[first] {
+            continue;
+        }
+        visited[first] = true;
+        let mut component = vec![first];
+        let mut next = 0;
+        while next < component.len() {
+            for cell in &constraints[component[next]].cells {
+                for &other in &constraints_of_cell[cell] {
+                    if !visited[other] {
+                        visited[other] = true;
+                        component.push(other);
+                    }
+                }
+            }
+            next += 1;
+        }
+        components.push(component);
+    }
+    components
+}
+
+// counts the placements of bombs in the unknown cells of a component, by number of bombs
+struct Placements {
+    cells: Vec<(usize, usize)>,
+    // placements[k] is the number of placements with k bombs
+    placements: Vec<f64>,
+    // bomb_placements[i][k] is how many of them have a bomb in cells[i]
+    bomb_placements: Vec<Vec<f64>>,
+}
+
+// the unknown cells around the same numbers, they only differ in how many bombs they hold
+struct Group {
+    cells: Vec<(usize, usize)>,
+    constraints: Vec<usize>,
+}
+
+// the state of the backtracking over the groups
+struct Counter<'a> {
+    constraints: &'a [&'a Constraint],
+    groups: Vec<Group>,
+    // ways_to_place[g][j] is the number of ways to put j bombs in group g
+    ways_to_place: Vec<Vec<f64>>,
+    placed_bombs: Vec<usize>,
+    open_cells: Vec<usize>,
+    group_bombs: Vec<usize>,
+    steps: usize,
+    placements: Vec<f64>,
+    group_bomb_placements: Vec<Vec<f64>>,
+}
+
+fn binomials(n: usize) -> Vec<f64> {
+    let mut row = vec![1.0; n + 1];
+    for k in 1..n {
+        row[k] = row[k - 1] * (n - k + 1) as f64 / k as f64;
+    }
+    row
+}
+
+fn count_placements(constraints: &[&Constraint]) -> Option<Placements> {
+    let mut groups: Vec<Group> = Vec::new();
+    for constraint in constraints {
+        for cell in &constraint.cells {
+            if groups.iter().any(|group| group.cells.contains(cell)) {
+                continue;
+            }
+            let cell_constraints: Vec<usize> = (0..constraints.len())
+                .filter(|&i| constraints[i].cells.binary_search(cell).is_ok())
+                .collect();
+            match groups
+                .iter_mut()
+                .find(|group| group.constraints == cell_constraints)
+            {
+                Some(group) => group.cells.push(*cell),
+                None => groups.push(Group {
+                    cells: vec![*cell],
+                    constraints: cell_constraints,
+                }),
+            }
+        }
+    }
+
+    let number_of_cells: usize = groups.iter().map(|group| group.cells.len()).sum();
+    let mut counter = Counter {
+        constraints,
+        ways_to_place: groups
+            .iter()
+            .map(|group| binomials(group.cells.len()))
+            .collect(),
+        placed_bombs: vec![0; constraints.len()],
+        open_cells: constraints
+            .iter()
+            .map(|constraint| constraint.cells.len())
+            .collect(),
+        group_bombs: Vec::new(),
+        steps: 0,
+        placements: vec![0.0; number_of_cells + 1],
+        group_bomb_placements: vec![vec![0.0; number_of_cells + 1]; groups.len()],
+        groups,
+    };
+    if !counter.place_next(1.0, 0) {
+        return None;
+    }
+
+    let mut result = Placements {
+        cells: Vec::new(),
+        placements: counter.placements,
+        bomb_placements: Vec::new(),
+    };
+    for (group, group_bomb_placements) in counter.groups.iter().zip(counter.group_bomb_placements) {
+        for cell in &group.cells {
+            result.cells.push(*cell);
+            result.bomb_placements.push(
+                group_bomb_placements
+                    .iter()
+                    .map(|placements| placements / group.cells.len() as f64)
+                    .collect(),
+            );
+        }
+    }
+    Some(result)
+}
+
+impl Counter<'_> {
+    // returns false when it takes too many steps
+    fn place_next(&mut self, ways: f64, bombs: usize) -> bool {
+        self.steps += 1;
+        if self.steps > MAX_PLACEMENT_STEPS {
+            return false;
+        }
+        let index = self.group_bombs.len();
+        if index == self.groups.len() {
+            self.placements[bombs] += ways;
+            for (group_index, group_bombs) in self.group_bombs.iter().enumerate() {
+                self.group_bomb_placements[group_index][bombs] += ways * *group_bombs as f64;
+            }
+            return true;
+        }
+
+        let size = self.groups[index].cells.len();
+        for group_bombs in 0..=size {
+            let mut possible = true;
+            for &constraint in &self.groups[index].constraints {
+                self.placed_bombs[constraint] += group_bombs;
+                self.open_cells[constraint] -= size;
+                let needed = self.constraints[constraint].bombs;
+                possible &= self.placed_bombs[constraint] <= needed
+                    && self.placed_bombs[constraint] + self.open_cells[constraint] >= needed;
+            }
+            self.group_bombs.push(group_bombs);
+            let finished = !possible
+                || self.place_next(
+                    ways * self.ways_to_place[index][group_bombs],
+                    bombs + group_bombs,
+                );
+            self.group_bombs.pop();
+            for &constraint in &self.groups[index].constraints {
+                self.placed_bombs[constraint] -= group_bombs;
+                self.open_cells[constraint] += size;
+            }
+            if !finished {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl Deduction {
+    fn add(&mut self, cells: &[(usize, usize)], bombs: usize, reason: Reason) {
+        if bombs == 0 {
+            for index in cells {
+                if !self.safe_cells.contains(index) {
+                    self.safe_cells.push(*index);
+                    self.explain(*index, false, &reason);
+                }
+            }
+        } else if bombs == cells.len() {
+            for index in cells {
+                if !self.bomb_cells.contains(index) {
+                    self.bomb_cells.push(*index);
+                    self.explain(*index, true, &reason);
+                }
+            }
+        }
+    }
+
+    fn explain(&mut self, cell: (usize, usize), is_bomb: bool, reason: &Reason) {
+        self.explanations.push(Explanation {
+            cell,
+            is_bomb,
+            reason: reason.clone(),
+        });
+    }
+
+    fn is_empty(&self) -> bool {
+        self.safe_cells.is_empty() && self.bomb_cells.is_empty()
+    }
+}
+
+// uses only the visible state of the board, never the hidden bombs;
+// the rules count one bomb in a cell, so nothing is proven when a cell can hold more
+pub fn deduce(game: &Game) -> Deduction {
+    let mut deduction = Deduction {
+        safe_cells: Vec::new(),
+        bomb_cells: Vec::new(),
+        explanations: Vec::new(),
+    };
+    if game.game_code.max_bombs_per_cell > 1 {
+        return deduction;
+    }
+    let constraints = collect_constraints(game);
+
+    // single number: all of its unknown cells are safe, or all of them are bombs
+    for constraint in &constraints {
+        let reason = if constraint.bombs == 0 {
+            Reason::NumberIsFlagged(constraint.number_cell)
+        } else {
+            Reason::NumberIsFull(constraint.number_cell)
+        };
+        deduction.add(&constraint.cells, constraint.bombs, reason);
+    }
+    if !deduction.is_empty() {
+        return deduction;
+    }
+
+    // two numbers: the cells of the bigger one that are not shared hold the difference
+    for small in &constraints {
+        for big in &constraints {
+            if small.cells.len() >= big.cells.len() || !is_subset(&small.cells, &big.cells) {
+                continue;
+            }
+            if big.bombs < small.bombs {
+                continue;
+            }
+            let difference: Vec<(usize, usize)> = big
+                .cells
+                .iter()
+                .filter(|index| small.cells.binary_search(index).is_err())
+                .cloned()
+                .collect();
+            let reason = Reason::SharedCells {
+                small: small.number_cell,
+                big: big.number_cell,
+                bombs: big.bombs - small.bombs,
+            };
+            deduction.add(&difference, big.bombs - small.bombs, reason);
+        }
+    }
+    if !deduction.is_empty() {
+        return deduction;
+    }
+
+    // all numbers: try every placement of bombs around the connected numbers
+    for component in connected_components(&constraints) {
+        let component: Vec<&Constraint> = component.iter().map(|&i| &constraints[i]).collect();
+        let placements = match count_placements(&component) {
+            Some(placements) => placements,
+            None => continue,
+        };
+        let total: f64 = placements.placements.iter().sum();
+        if total == 0.0 {
+            continue;
+        }
+        let reason = Reason::AllPlacements(
+            component
+                .iter()
+                .map(|constraint| constraint.number_cell)
+                .collect(),
+        );
+        for (cell, bomb_placements) in placements.cells.iter().zip(&placements.bomb_placements) {
+            let bomb_placements: f64 = bomb_placements.iter().sum();
+            if bomb_placements == 0.0 {
+                deduction.add(&[*cell], 0, reason.clone());
+            } else if bomb_placements == total {
+                deduction.add(&[*cell], 1, reason.clone());
+            }
+        }
+    }
+
+    deduction
+}
+
+// the number of placements of the bombs of two parts of the board together, by number of bombs
+fn combine(first: &[f64], second: &[f64]) -> Vec<f64> {
+    let mut combined = vec![0.0; first.len() + second.len() - 1];
+    for (i, a) in first.iter().enumerate() {
+        for (j, b) in second.iter().enumerate() {
+            combined[i + j] += a * b;
+        }
+    }
+    combined
+}
+
+// the chance of a bomb in every unknown cell, from the visible board and the number of bombs,
+// the discovered and flagged cells have none;
+// the cells of a component with too many placements to count get the chance of the cells away
+// from the numbers; returns None if the flags do not fit the numbers,
+// or a cell can hold more than one bomb
+pub fn bomb_probabilities(game: &Game) -> Option<Vec<Vec<Option<f64>>>> {
+    if game.game_code.max_bombs_per_cell > 1 {
+        return None;
+    }
+    let constraints = collect_constraints(game);
+    let mut components: Vec<Placements> = Vec::new();
+    for component in connected_components(&constraints) {
+        let component: Vec<&Constraint> = component.iter().map(|&i| &constraints[i]).collect();
+        if let Some(placements) = count_placements(&component) {
+            components.push(placements);
+        }
+    }
+
+    // the unknown cells away from the numbers share the bombs left by the numbers
+    let mut probabilities = vec![vec![None; game.size.1]; game.size.0];
+    let mut flags = 0;
+    let mut other_cells = 0;
+    for (row, cells) in game.cells.iter().enumerate() {
+        for (column, cell) in cells.iter().enumerate() {
+            if cell.is_flagged {
+                flags += 1;
+            } else if !cell.is_discovered {
+                probabilities[row][column] = Some(0.0);
+                other_cells += 1;
+            }
+        }
+    }
+    other_cells -= components
+        .iter()
+        .map(|placements| placements.cells.len())
+        .sum::<usize>();
+    let bombs = game.number_of_bombs.checked_sub(flags)?;
+
+    // the placements of the other cells are weighted relative to the largest one, in logarithms
+    let mut ln_factorials = vec![0.0; other_cells + 1];
+    for n in 1..=other_cells {
+        ln_factorials[n] = ln_factorials[n - 1] + (n as f64).ln();
+    }
+    let ln_other_placements = |number_of_bombs: usize| -> Option<f64> {
+        let other_bombs = bombs.checked_sub(number_of_bombs)?;
+        if other_bombs > other_cells {
+            return None;
+        }
+        Some(
+            ln_factorials[other_cells]
+                - ln_factorials[other_bombs]
+                - ln_factorials[other_cells - other_bombs],
+        )
+    };
+    let all_placements = components.iter().fold(vec![1.0], |all, placements| {
+        combine(&all, &placements.placements)
+    });
+    let largest = (0..all_placements.len())
+        .filter(|&k| all_placements[k] > 0.0)
+        .filter_map(ln_other_placements)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let other_placements = |number_of_bombs: usize| {
+        ln_other_placements(number_of_bombs).map_or(0.0, |ln| (ln - largest).exp())
+    };
+
+    let total: f64 = (0..all_placements.len())
+        .map(|k| all_placements[k] * other_placements(k))
+        .sum();
+    if total <= 0.0 {
+        return None;
+    }
+
+    for (index, placements) in components.iter().enumerate() {
+        let rest = components
+            .iter()
+            .enumerate()
+            .filter(|(other, _)| *other != index)
+            .fold(vec![1.0], |rest, (_, other)| {
+                combine(&rest, &other.placements)
+            });
+        // the weight of k bombs in this component, with every placement of the rest
+        let weights: Vec<f64> = (0..placements.placements.len())
+            .map(|k| {
+                rest.iter()
+                    .enumerate()
+                    .map(|(j, rest_placements)| rest_placements * other_placements(k + j))
+                    .sum()
+            })
+            .collect();
+        for (cell, bomb_placements) in placements.cells.iter().zip(&placements.bomb_placements) {
+            let weighted: f64 = bomb_placements
+                .iter()
+                .zip(&weights)
+                .map(|(placements, weight)| placements * weight)
+                .sum();
+            probabilities[cell.0][cell.1] = Some(weighted / total);
+        }
+    }
+
+    if other_cells > 0 {
+        let other_bombs: f64 = (0..all_placements.len())
+            .map(|k| all_placements[k] * other_placements(k) * bombs.saturating_sub(k) as f64)
+            .sum();
+        let probability = other_bombs / total / other_cells as f64;
+        let frontier: Vec<(usize, usize)> = components
+            .iter()
+            .flat_map(|placements| placements.cells.iter().cloned())
+            .collect();
+        for (row, cells) in probabilities.iter_mut().enumerate() {
+            for (column, cell) in cells.iter_mut().enumerate() {
+                if cell.is_some() && !frontier.contains(&(row, column)) {
+                    *cell = Some(probability);
+                }
+            }
+        }
+    }
+    Some(probabilities)
+}
+
+fn format_cell((row, column): (usize, usize)) -> String {
+    format!("row {} col {}", row + 1, column + 1)
+}
+
+fn format_number(game: &Game, cell: (usize, usize)) -> String {
+    format!(
+        "the {} at {}",
+        game.cells[cell.0][cell.1].number_of_adjusted_bombs,
+        format_cell(cell)
+    )
+}
+
+impl Explanation {
+    // one line for the player, like "row 3 col 4 is safe: the 1 at row 2 col 3 has all its bombs flagged"
+    pub fn describe(&self, game: &Game) -> String {
+        let reason = match &self.reason {
+            Reason::NumberIsFlagged(number_cell) => {
+                format!(
+                    "{} has all its bombs flagged",
+                    format_number(game, *number_cell)
+                )
+            }
+            Reason::NumberIsFull(number_cell) => format!(
+                "{} has as many unknown cells as missing bombs",
+                format_number(game, *number_cell)
+            ),
+            Reason::SharedCells { small, big, bombs } => format!(
+                "the unknown cells of {} are around {} too, its other cells hold {} bombs",
+                format_number(game, *small),
+                format_number(game, *big),
+                bombs
+            ),
+            Reason::AllPlacements(number_cells) => {
+                let mut numbers: Vec<String> = number_cells
+                    .iter()
+                    .take(3)
+                    .map(|number_cell| format_number(game, *number_cell))
+                    .collect();
+                if number_cells.len() > 3 {
+                    numbers.push(format!("{} more", number_cells.len() - 3));
+                }
+                format!(
+                    "every placement of bombs around {} agrees",
+                    numbers.join(", ")
+                )
+            }
+        };
+        format!(
+            "{} is {}: {}",
+            format_cell(self.cell),
+            if self.is_bomb { "a bomb" } else { "safe" },
+            reason
+        )
+    }
+}
+
+// the first safe cell with its reason, or else the first bomb
+pub fn explain(game: &Game) -> Option<Explanation> {
+    let deduction = deduce(game);
+    let safe = deduction
+        .explanations
+        .iter()
+        .find(|explanation| !explanation.is_bomb);
+    safe.or(deduction.explanations.first()).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::init_game_from_layout;
+
+    use super::*;
+
+    #[test]
+    fn deduce_single_number() {
+        // B 1 .
+        // 1 1 .
+        // . . .
+        let mut game = init_game_from_layout((3, 3), &[(0, 0)]);
+        game.cells[1][1].is_discovered = true;
+
+        let deduction = deduce(&game);
+        assert!(deduction.safe_cells.is_empty());
+        assert!(deduction.bomb_cells.is_empty());
+
+        game.cells[0][1].is_discovered = true;
+        game.cells[1][0].is_discovered = true;
+        game.cells[0][2].is_discovered = true;
+        game.cells[1][2].is_discovered = true;
+        game.cells[2][0].is_discovered = true;
+        game.cells[2][1].is_discovered = true;
+        game.cells[2][2].is_discovered = true;
+        let deduction = deduce(&game);
+        assert_eq!(deduction.bomb_cells, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn deduce_with_subset() {
+        // every number has one bomb among two or three unknown cells,
+        // the outer numbers share their cells with the middle one
+        // . B .
+        // 1 1 1
+        // . . .
+        let mut game = init_game_from_layout((3, 3), &[(0, 1)]);
+        for column in 0..3 {
+            game.cells[1][column].is_discovered = true;
+            game.cells[2][column].is_discovered = true;
+        }
+
+        let deduction = deduce(&game);
+        assert_eq!(deduction.safe_cells, vec![(0, 2), (0, 0)]);
+        assert!(deduction.bomb_cells.is_empty());
+        assert_eq!(
+            explain(&game).unwrap().describe(&game),
+            "row 1 col 3 is safe: the unknown cells of the 1 at row 2 col 1 are around the 1 at row 2 col 2 too, its other cells hold 0 bombs"
+        );
+    }
+
+    #[test]
+    fn probabilities_count_the_bombs_away_from_the_numbers() {
+        // one bomb is next to the 1, the other one is among the 4 cells away from it
+        // 1 . . .
+        // . . . .
+        let mut game = init_game_from_layout((2, 4), &[(0, 1), (1, 3)]);
+        game.cells[0][0].is_discovered = true;
+
+        let probabilities = bomb_probabilities(&game).unwrap();
+        // 3 cells around the 1 with one bomb, 4 away with one bomb: the placements with
+        // the bomb of the 1 in a cell have 4 ways for the other bomb, so each is 1/3
+        assert_eq!(probabilities[0][0], None);
+        assert!((probabilities[0][1].unwrap() - 1.0 / 3.0).abs() < 1e-9);
+        assert!((probabilities[1][1].unwrap() - 1.0 / 3.0).abs() < 1e-9);
+        assert!((probabilities[0][3].unwrap() - 0.25).abs() < 1e-9);
+
+        // a flag on the 1 leaves its other cells safe
+        game.cells[0][1].is_flagged = true;
+        let probabilities = bomb_probabilities(&game).unwrap();
+        assert_eq!(probabilities[0][1], None);
+        assert_eq!(probabilities[1][0], Some(0.0));
+        assert!((probabilities[1][2].unwrap() - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn probabilities_skip_only_the_component_too_large_to_count() {
+        // the numbers in every other cell of a long row have too many placements to count,
+        // the 3 in the corner is counted on its own
+        let mut bombs: Vec<(usize, usize)> = (0..39)
+            .filter(|column| column % 3 == 0)
+            .flat_map(|column| [(0, column), (2, column + 1)])
+            .collect();
+        bombs.extend([(3, 38), (3, 39), (4, 38)]);
+        let mut game = init_game_from_layout((5, 40), &bombs);
+        for column in (0..40).step_by(2) {
+            game.cells[1][column].is_discovered = true;
+        }
+        game.cells[4][39].is_discovered = true;
+        let constraints = collect_constraints(&game);
+        let row: Vec<&Constraint> = constraints
+            .iter()
+            .filter(|constraint| constraint.number_cell.0 == 1)
+            .collect();
+        assert!(count_placements(&row).is_none());
+
+        let probabilities = bomb_probabilities(&game).unwrap();
+        assert_eq!(probabilities[3][38], Some(1.0));
+        assert_eq!(probabilities[4][38], Some(1.0));
+        // the cells of the row are guessed like the cells away from the numbers
+        let away = probabilities[3][0].unwrap();
+        assert!(away > 0.0 && away < 1.0);
+        assert_eq!(probabilities[0][5], Some(away));
+        assert_eq!(probabilities[2][20], Some(away));
+    }
+
+    #[test]
+    fn deduce_from_all_placements() {
+        // no number or pair of numbers is enough, but all three together are
+        // 1 . B B
+        // B . 4 .
+        // 2 B B .
+        let mut game = init_game_from_layout((3, 4), &[(0, 2), (0, 3), (1, 0), (2, 1), (2, 2)]);
+        game.cells[0][0].is_discovered = true;
+        game.cells[1][2].is_discovered = true;
+        game.cells[2][0].is_discovered = true;
+
+        let deduction = deduce(&game);
+        assert_eq!(deduction.safe_cells, vec![(0, 1)]);
+        assert_eq!(deduction.bomb_cells, vec![(2, 1)]);
+        assert_eq!(
+            explain(&game).unwrap().describe(&game),
+            "row 1 col 2 is safe: every placement of bombs around the 1 at row 1 col 1, the 4 at row 2 col 3, the 2 at row 3 col 1 agrees"
+        );
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Game, GameStatus};
+
+// tagged games are kept out of the pure best times
+pub const NO_GUESS_TAG: &str = "no-guess";
+pub const HINT_TAG: &str = "hint";
+pub const UNDO_TAG: &str = "undo";
+
+// one finished game, stored as a line of json in the stats file
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct GameRecord {
+    pub finished_at: u64,
+    pub width: usize,
+    pub height: usize,
+    pub mines: usize,
+    pub milliseconds: u64,
+    pub won: bool,
+    pub hints_used: usize,
+    pub seed: u64,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // the options that change the rules, like hex or wrap, kept apart from the standard boards
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub variant: String,
+}
+
+impl GameRecord {
+    pub fn is_pure(&self) -> bool {
+        self.tags.is_empty()
+    }
+}
+
+pub fn record_game(game: &Game, elapsed: Duration) -> GameRecord {
+    let mut tags: Vec<String> = Vec::new();
+    if game.game_code.no_guess {
+        tags.push(NO_GUESS_TAG.to_owned());
+    }
+    if game.hints_used > 0 {
+        tags.push(HINT_TAG.to_owned());
+    }
+    if game.undos_used > 0 {
+        tags.push(UNDO_TAG.to_owned());
+    }
+    let finished_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0);
+
+    GameRecord {
+        finished_at,
+        width: game.size.1,
+        height: game.size.0,
+        mines: game.number_of_bombs,
+        milliseconds: elapsed.as_millis() as u64,
+        won: game.status == GameStatus::Won,
+        hints_used: game.hints_used,
+        seed: game.game_code.seed,
+        tags,
+        variant: game.game_code.variant(),
+    }
+}
+
+// the stats file in the data directory, like ~/.local/share/minesweeper/stats.jsonl
+pub fn stats_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|path| path.join("minesweeper").join("stats.jsonl"))
+}
+
+pub fn append_record(path: &Path, record: &GameRecord) -> Result<(), String> {
+    let write_error = |e: std::io::Error| format!("could not write {}: {}", path.display(), e);
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory).map_err(write_error)?;
+    }
+    let line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(write_error)?;
+    writeln!(file, "{}", line).map_err(write_error)
+}
+
+pub fn read_records(path: &Path) -> Result<Vec<GameRecord>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let mut records: Vec<GameRecord> = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(line)
+            .map_err(|e| format!("line {} of {} is broken: {}", index + 1, path.display(), e))?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+#[derive(PartialEq, Debug)]
+pub struct Summary {
+    pub played: usize,
+    pub won: usize,
+    pub current_streak: usize,
+    pub best_streak: usize,
+    pub best_milliseconds: Option<u64>,
+    pub best_pure_milliseconds: Option<u64>,
+}
+
+// the records are in the order they were played
+pub fn summarize(records: &[&GameRecord]) -> Summary {
+    let mut summary = Summary {
+        played: records.len(),
+        won: 0,
+        current_streak: 0,
+        best_streak: 0,
+        best_milliseconds: None,
+        best_pure_milliseconds: None,
+    };
+    for record in records {
+        if !record.won {
+            summary.current_streak = 0;
+            continue;
+        }
+        summary.won += 1;
+        summary.current_streak += 1;
+        summary.best_streak = summary.best_streak.max(summary.current_streak);
+        summary.best_milliseconds = Some(
+            summary
+                .best_milliseconds
+                .map_or(record.milliseconds, |best| best.min(record.milliseconds)),
+        );
+        if record.is_pure() {
+            summary.best_pure_milliseconds = Some(
+                summary
+                    .best_pure_milliseconds
+                    .map_or(record.milliseconds, |best| best.min(record.milliseconds)),
+            );
+        }
+    }
+    summary
+}
+
+fn format_milliseconds(milliseconds: Option<u64>) -> String {
+    match milliseconds {
+        Some(milliseconds) => format!("{:.1}s", milliseconds as f64 / 1000.0),
+        None => "-".to_owned(),
+    }
+}
+
+fn win_rate(summary: &Summary) -> String {
+    format!("{:.0}%", 100.0 * summary.won as f64 / summary.played as f64)
+}
+
+// the overall numbers, then one line per board size and mine count
+pub fn format_report(records: &[GameRecord]) -> String {
+    if records.is_empty() {
+        return "no finished games yet".to_owned();
+    }
+
+    let all = summarize(&records.iter().collect::<Vec<&GameRecord>>());
+    let mut report = format!(
+        "played {}, won {} ({}), current streak {}, best streak {}\n\n",
+        all.played,
+        all.won,
+        win_rate(&all),
+        all.current_streak,
+        all.best_streak
+    );
+
+    let mut groups: BTreeMap<(usize, usize, usize, &str), Vec<&GameRecord>> = BTreeMap::new();
+    for record in records {
+        groups
+            .entry((
+                record.width * record.height,
+                record.width,
+                record.mines,
+                &record.variant,
+            ))
+            .or_default()
+            .push(record);
+    }
+    report += &format!(
+        "{:<8} {:>5} {:>6} {:>5} {:>8} {:>6} {:>11} {:>9} {:>11}  {}\n",
+        "size",
+        "mines",
+        "played",
+        "won",
+        "win rate",
+        "streak",
+        "best streak",
+        "best time",
+        "pure best",
+        "variant"
+    );
+    for group in groups.values() {
+        let summary = summarize(group);
+        report += &format!(
+            "{:<8} {:>5} {:>6} {:>5} {:>8} {:>6} {:>11} {:>9} {:>11}  {}\n",
+            format!("{}x{}", group[0].width, group[0].height),
+            group[0].mines,
+            summary.played,
+            summary.won,
+            win_rate(&summary),
+            summary.current_streak,
+            summary.best_streak,
+            format_milliseconds(summary.best_milliseconds),
+            format_milliseconds(summary.best_pure_milliseconds),
+            if group[0].variant.is_empty() {
+                "standard"
+            } else {
+                &group[0].variant
+            }
+        );
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::init_game_from_code;
+
+    use super::*;
+
+    fn record(won: bool, milliseconds: u64, tags: &[&str]) -> GameRecord {
+        GameRecord {
+            finished_at: 0,
+            width: 9,
+            height: 9,
+            mines: 10,
+            milliseconds,
+            won,
+            hints_used: 0,
+            seed: 0,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            variant: String::new(),
+        }
+    }
+
+    #[test]
+    fn summarize_streaks_and_best_times() {
+        let records = [
+            record(true, 50_000, &[]),
+            record(true, 30_000, &[HINT_TAG]),
+            record(false, 10_000, &[]),
+            record(true, 45_000, &[NO_GUESS_TAG]),
+            record(true, 20_000, &[UNDO_TAG]),
+        ];
+        let summary = summarize(&records.iter().collect::<Vec<&GameRecord>>());
+
+        assert_eq!(
+            summary,
+            Summary {
+                played: 5,
+                won: 4,
+                current_streak: 2,
+                best_streak: 2,
+                best_milliseconds: Some(20_000),
+                best_pure_milliseconds: Some(50_000),
+            }
+        );
+    }
+
+    #[test]
+    fn variants_have_their_own_best_times() {
+        let game_code = "9x9-10-safe-knight-wrap-1".parse().unwrap();
+        let game = init_game_from_code(&game_code).unwrap();
+        let knight = GameRecord {
+            milliseconds: 5_000,
+            ..record_game(&game, Duration::ZERO)
+        };
+        assert_eq!(knight.variant, "knight-wrap");
+        assert!(knight.is_pure());
+
+        let report = format_report(&[record(true, 50_000, &[]), knight]);
+        let rows: Vec<&str> = report.lines().skip(3).collect();
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].ends_with("50.0s  standard"));
+        assert!(rows[1].ends_with("-  knight-wrap"));
+    }
+
+    #[test]
+    fn append_and_read_records() {
+        let path = std::env::temp_dir()
+            .join(format!("minesweeper-stats-{}", std::process::id()))
+            .join("stats.jsonl");
+        append_record(&path, &record(true, 1_000, &[])).unwrap();
+        append_record(&path, &record(false, 2_000, &[HINT_TAG])).unwrap();
+
+        let records = read_records(&path).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(
+            records,
+            vec![record(true, 1_000, &[]), record(false, 2_000, &[HINT_TAG])]
+        );
+    }
+}
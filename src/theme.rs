@@ -1,4 +1,11 @@
-use crossterm::style::{Color, ResetColor, SetForegroundColor};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crossterm::style::{Color, ResetColor, SetBackgroundColor, SetForegroundColor};
+use serde::Deserialize;
+use unicode_width::UnicodeWidthStr;
+
+use minesweeper::game::Cell;
 
 #[derive(PartialEq)]
 pub struct Theme {
@@ -21,6 +28,10 @@ pub struct Theme {
     pub edge_bottom: String,
     pub edge_left: String,
     pub edge_right: String,
+    // the outer border of a board that wraps around, to show that it goes on at the other side
+    pub wrap_line_horizontal: String,
+    pub wrap_line_vertical: String,
+    pub wrap_corner: String,
 
     pub bomb: String,
     pub flag: String,
@@ -32,10 +43,12 @@ pub struct Theme {
     pub highlight_corner_on_selection: bool,
 
     pub line_color: Option<Color>,
+    // the background behind the flag glyph, the glyph itself stays plain
+    pub flag_background_color: Option<Color>,
 }
 
-pub fn get_theme(theme_name: &String) -> Option<Theme> {
-    match theme_name.as_str() {
+pub fn get_theme(theme_name: &str) -> Option<Theme> {
+    match theme_name {
         "border" => Some(border_theme()),
         "dark_border" => Some(dark_border_theme()),
         "borderless" => Some(borderless_theme()),
@@ -43,11 +56,315 @@ pub fn get_theme(theme_name: &String) -> Option<Theme> {
     }
 }
 
-pub fn rotate_theme_name(theme_name: &String) -> String {
-    match theme_name.as_str() {
-        "dark_border" => "borderless".to_owned(),
-        "borderless" => "border".to_owned(),
-        _ => "dark_border".to_owned(),
+// the built-in themes in the order of the rotation
+pub const BUILT_IN_THEME_NAMES: [&str; 3] = ["dark_border", "borderless", "border"];
+
+// a built-in theme, or else the theme file with that name
+pub fn load_theme(theme_name: &str) -> Result<Theme, String> {
+    if let Some(theme) = get_theme(theme_name) {
+        return Ok(theme);
+    }
+    // a theme file is only looked up in the themes directory
+    if theme_name.contains(['/', '\\']) || theme_name.contains("..") {
+        return Err(format!(
+            "the theme name {} has a path in it, use the name of a file in the themes directory",
+            theme_name
+        ));
+    }
+    match themes_dir() {
+        Some(directory) if directory.join(format!("{}.toml", theme_name)).is_file() => {
+            read_theme_file(&directory.join(format!("{}.toml", theme_name)))
+        }
+        _ => Err(format!("not found theme {}", theme_name)),
+    }
+}
+
+// the built-in themes, then the theme files by name
+pub fn theme_names() -> Vec<String> {
+    let mut names: Vec<String> = BUILT_IN_THEME_NAMES.map(str::to_owned).to_vec();
+    for (name, _) in theme_files() {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+pub fn rotate_theme_name(theme_name: &str) -> String {
+    let names = theme_names();
+    match names.iter().position(|name| name == theme_name) {
+        Some(index) => names[(index + 1) % names.len()].clone(),
+        None => names[0].clone(),
+    }
+}
+
+// the theme files, like ~/.config/minesweeper/themes/ocean.toml
+pub fn themes_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|path| path.join("minesweeper").join("themes"))
+}
+
+// the name and path of every theme file, sorted by name
+pub fn theme_files() -> Vec<(String, PathBuf)> {
+    let mut files: Vec<(String, PathBuf)> = Vec::new();
+    if let Some(entries) = themes_dir().and_then(|directory| fs::read_dir(directory).ok()) {
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "toml")
+            {
+                if let Some(name) = path.file_stem().and_then(|name| name.to_str()) {
+                    files.push((name.to_owned(), path.clone()));
+                }
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+pub fn read_theme_file(path: &Path) -> Result<Theme, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let name = path
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    parse_theme(name, &content)
+        .map_err(|e| format!("{} is not a valid theme: {}", path.display(), e))
+}
+
+// a theme file sets some of the fields of a base theme, every field is optional
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    // the built-in theme for the missing fields, border by default
+    base: Option<String>,
+    cell_horizontal_padding_enabled: Option<bool>,
+    cell_horizontal_padding: Option<String>,
+    outer_border_enabled: Option<bool>,
+    inner_border_row_enabled: Option<bool>,
+    inner_border_column_enabled: Option<bool>,
+    line_horizontal: Option<String>,
+    line_vertical: Option<String>,
+    line_cross: Option<String>,
+    corner_top_left: Option<String>,
+    corner_top_right: Option<String>,
+    corner_bottom_left: Option<String>,
+    corner_bottom_right: Option<String>,
+    edge_top: Option<String>,
+    edge_bottom: Option<String>,
+    edge_left: Option<String>,
+    edge_right: Option<String>,
+    wrap_line_horizontal: Option<String>,
+    wrap_line_vertical: Option<String>,
+    wrap_corner: Option<String>,
+    bomb: Option<String>,
+    flag: Option<String>,
+    empty: Option<String>,
+    unknown: Option<String>,
+    // six colours for the numbers from 1, or an empty list for no colours
+    number_colors: Option<Vec<String>>,
+    colored_numbers_on_selection: Option<bool>,
+    highlight_corner_on_selection: Option<bool>,
+    // a colour, or "none" for the colour of the terminal
+    line_color: Option<String>,
+    // a colour, or "none" for no background behind the flags
+    flag_background_color: Option<String>,
+}
+
+pub fn parse_theme(name: &str, content: &str) -> Result<Theme, String> {
+    let file: ThemeFile = toml::from_str(content).map_err(|e| {
+        // the error points at the value, the field is at the start of its line
+        let field = e.span().and_then(|span| {
+            let line_start = content[..span.start].rfind('\n').map_or(0, |i| i + 1);
+            let (field, _) = content[line_start..].split_once('=')?;
+            Some(field.trim().to_owned())
+        });
+        match field {
+            Some(field) if !e.message().starts_with("unknown field") => {
+                format!("field `{}`: {}", field, e.message())
+            }
+            _ => e.message().to_owned(),
+        }
+    })?;
+    let base = file.base.as_deref().unwrap_or("border");
+    let mut theme = get_theme(base).ok_or_else(|| {
+        format!(
+            "field `base` has an unknown theme {}, use one of {}",
+            base,
+            BUILT_IN_THEME_NAMES.join(", ")
+        )
+    })?;
+    theme.name = name.to_owned();
+
+    macro_rules! set_fields {
+        ($($field:ident),*) => {
+            $(if let Some(value) = file.$field {
+                theme.$field = value;
+            })*
+        };
+    }
+    set_fields!(
+        cell_horizontal_padding_enabled,
+        cell_horizontal_padding,
+        outer_border_enabled,
+        inner_border_row_enabled,
+        inner_border_column_enabled,
+        line_horizontal,
+        line_vertical,
+        line_cross,
+        corner_top_left,
+        corner_top_right,
+        corner_bottom_left,
+        corner_bottom_right,
+        edge_top,
+        edge_bottom,
+        edge_left,
+        edge_right,
+        wrap_line_horizontal,
+        wrap_line_vertical,
+        wrap_corner,
+        bomb,
+        flag,
+        empty,
+        unknown,
+        colored_numbers_on_selection,
+        highlight_corner_on_selection
+    );
+    if let Some(colors) = file.number_colors {
+        theme.number_colors = match colors.len() {
+            0 => None,
+            6 => {
+                let mut number_colors = [Color::Reset; 6];
+                for (number_color, color) in number_colors.iter_mut().zip(&colors) {
+                    *number_color = parse_color("number_colors", color)?;
+                }
+                Some(number_colors)
+            }
+            length => {
+                return Err(format!(
+                    "field `number_colors` has {} colours, it needs 6 or none",
+                    length
+                ))
+            }
+        };
+    }
+    if let Some(color) = file.line_color {
+        theme.line_color = match color.as_str() {
+            "none" => None,
+            _ => Some(parse_color("line_color", &color)?),
+        };
+    }
+    if let Some(color) = file.flag_background_color {
+        theme.flag_background_color = match color.as_str() {
+            "none" => None,
+            _ => Some(parse_color("flag_background_color", &color)?),
+        };
+    }
+
+    check_theme(&theme)?;
+    Ok(theme)
+}
+
+// a colour name like dark_blue, or a hex colour like #69c9fa
+fn parse_color(field: &str, color: &str) -> Result<Color, String> {
+    let parsed = match color {
+        "black" => Some(Color::Black),
+        "dark_grey" => Some(Color::DarkGrey),
+        "red" => Some(Color::Red),
+        "dark_red" => Some(Color::DarkRed),
+        "green" => Some(Color::Green),
+        "dark_green" => Some(Color::DarkGreen),
+        "yellow" => Some(Color::Yellow),
+        "dark_yellow" => Some(Color::DarkYellow),
+        "blue" => Some(Color::Blue),
+        "dark_blue" => Some(Color::DarkBlue),
+        "magenta" => Some(Color::Magenta),
+        "dark_magenta" => Some(Color::DarkMagenta),
+        "cyan" => Some(Color::Cyan),
+        "dark_cyan" => Some(Color::DarkCyan),
+        "white" => Some(Color::White),
+        "grey" => Some(Color::Grey),
+        _ => color
+            .strip_prefix('#')
+            .filter(|hex| hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .map(|hex| Color::Rgb {
+                r: u8::from_str_radix(&hex[0..2], 16).unwrap(),
+                g: u8::from_str_radix(&hex[2..4], 16).unwrap(),
+                b: u8::from_str_radix(&hex[4..6], 16).unwrap(),
+            }),
+    };
+    parsed.ok_or_else(|| {
+        format!(
+            "field `{}` has an unknown colour {:?}, use a name like dark_blue or a hex colour like #69c9fa",
+            field, color
+        )
+    })
+}
+
+// the board is drawn one column per piece, so every glyph must take one column,
+// or none for the borders that are turned off
+fn check_theme(theme: &Theme) -> Result<(), String> {
+    let outer = theme.outer_border_enabled;
+    let row = theme.inner_border_row_enabled;
+    let column = theme.inner_border_column_enabled;
+    let glyphs: [(&str, &str, bool); 19] = [
+        (
+            "cell_horizontal_padding",
+            &theme.cell_horizontal_padding,
+            theme.cell_horizontal_padding_enabled,
+        ),
+        ("line_horizontal", &theme.line_horizontal, outer || row),
+        ("line_vertical", &theme.line_vertical, outer || column),
+        ("line_cross", &theme.line_cross, row && column),
+        ("corner_top_left", &theme.corner_top_left, outer),
+        ("corner_top_right", &theme.corner_top_right, outer),
+        ("corner_bottom_left", &theme.corner_bottom_left, outer),
+        ("corner_bottom_right", &theme.corner_bottom_right, outer),
+        ("edge_top", &theme.edge_top, outer && column),
+        ("edge_bottom", &theme.edge_bottom, outer && column),
+        // a board that wraps around has an outer border in every theme
+        ("edge_left", &theme.edge_left, row),
+        ("edge_right", &theme.edge_right, row),
+        ("wrap_line_horizontal", &theme.wrap_line_horizontal, true),
+        ("wrap_line_vertical", &theme.wrap_line_vertical, true),
+        ("wrap_corner", &theme.wrap_corner, true),
+        ("bomb", &theme.bomb, true),
+        ("flag", &theme.flag, true),
+        ("empty", &theme.empty, true),
+        ("unknown", &theme.unknown, true),
+    ];
+    for (field, glyph, needed) in glyphs {
+        if glyph.chars().any(char::is_control) {
+            return Err(format!(
+                "field `{}` has a control character, only printable glyphs are supported",
+                field
+            ));
+        }
+        let width = glyph.width();
+        if width > 1 {
+            return Err(format!(
+                "field `{}` has {:?} which takes {} columns, only glyphs of one column are supported",
+                field, glyph, width
+            ));
+        }
+        if needed && width == 0 {
+            return Err(format!(
+                "field `{}` is empty, but the theme draws it, set it to a glyph of one column",
+                field
+            ));
+        }
+    }
+    Ok(())
+}
+
+// the number colours of the 'C' key: classic, pastel, or none
+pub fn get_palette(palette_name: &str) -> Option<Option<[Color; 6]>> {
+    match palette_name {
+        "classic" => Some(Some(THEME_COLOR_LIST_1)),
+        "pastel" => Some(Some(THEME_COLOR_LIST_2)),
+        "none" => Some(None),
+        _ => None,
     }
 }
 
@@ -79,6 +396,9 @@ pub fn border_theme() -> Theme {
         edge_bottom: '┴'.to_string(),
         edge_left: '├'.to_string(),
         edge_right: '┤'.to_string(),
+        wrap_line_horizontal: '╌'.to_string(),
+        wrap_line_vertical: '╎'.to_string(),
+        wrap_corner: '┼'.to_string(),
 
         bomb: 'B'.to_string(),
         flag: 'F'.to_string(),
@@ -89,6 +409,7 @@ pub fn border_theme() -> Theme {
         colored_numbers_on_selection: true,
         highlight_corner_on_selection: false,
         line_color: None,
+        flag_background_color: None,
     }
 }
 
@@ -112,6 +433,9 @@ pub fn borderless_theme() -> Theme {
         edge_bottom: "".to_string(),
         edge_left: "".to_string(),
         edge_right: "".to_string(),
+        wrap_line_horizontal: '╌'.to_string(),
+        wrap_line_vertical: '╎'.to_string(),
+        wrap_corner: '┼'.to_string(),
 
         bomb: 'B'.to_string(),
         flag: 'F'.to_string(),
@@ -122,6 +446,7 @@ pub fn borderless_theme() -> Theme {
         colored_numbers_on_selection: false,
         highlight_corner_on_selection: false,
         line_color: None,
+        flag_background_color: None,
     }
 }
 
@@ -129,12 +454,7 @@ pub fn dark_border_theme() -> Theme {
     let mut t = border_theme();
     t.name = "dark_border".to_owned();
     t.line_color = Some(Color::DarkGrey);
-    t.flag = format!(
-        "{}{}{}",
-        crossterm::style::SetBackgroundColor(Color::DarkGrey).to_string(),
-        t.flag,
-        crossterm::style::ResetColor.to_string()
-    );
+    t.flag_background_color = Some(Color::DarkGrey);
 
     t
 }
@@ -182,32 +502,80 @@ const THEME_COLOR_LIST_2: [Color; 6] = [
 ];
 
 impl Theme {
+    // the content in pieces of one column each, the board makes room for the longest number
+    pub fn content_to_show(&self, cell: &Cell, selected: bool, width: usize) -> Vec<String> {
+        let content = if cell.is_discovered {
+            if cell.is_bomb {
+                self.format_symbol_count(&self.bomb, cell.number_of_bombs)
+            } else if cell.number_of_adjusted_bombs == 0 {
+                vec![self.empty.clone()]
+            } else {
+                self.format_number_of_adjusted_bombs(cell.number_of_adjusted_bombs, selected)
+            }
+        } else if cell.is_flagged {
+            self.format_symbol_count(&self.format_flag(), cell.number_of_flags)
+        } else {
+            return vec![self.unknown.clone(); width];
+        };
+        let mut pieces = vec![" ".to_owned(); width.saturating_sub(content.len())];
+        pieces.extend(content);
+        pieces
+    }
+
+    pub fn format_flag(&self) -> String {
+        match self.flag_background_color {
+            Some(color) => format!("{}{}{}", SetBackgroundColor(color), self.flag, ResetColor),
+            None => self.flag.clone(),
+        }
+    }
+
+    // a cell with several bombs or flags shows how many after the symbol, like F2
+    pub fn format_symbol_count(&self, symbol: &str, count: u8) -> Vec<String> {
+        if count > 1 {
+            vec![symbol.to_owned(), count.to_string()]
+        } else {
+            vec![symbol.to_owned()]
+        }
+    }
+
+    // one piece for each digit
     pub fn format_number_of_adjusted_bombs(
         &self,
-        number_of_adjusted_bombs: u8,
+        number_of_adjusted_bombs: usize,
         selected: bool,
-    ) -> String {
+    ) -> Vec<String> {
         let use_color =
-            (self.number_colors != None) && (!selected || self.colored_numbers_on_selection);
-
-        if use_color {
-            let number_colors = self.number_colors.unwrap();
-            format!(
-                "{}{}{}",
-                (match number_of_adjusted_bombs {
-                    1 => SetForegroundColor(number_colors[0]).to_string(),
-                    2 => SetForegroundColor(number_colors[1]).to_string(),
-                    3 => SetForegroundColor(number_colors[2]).to_string(),
-                    4 => SetForegroundColor(number_colors[3]).to_string(),
-                    5 => SetForegroundColor(number_colors[4]).to_string(),
-                    _ => SetForegroundColor(number_colors[5]).to_string(),
-                }),
-                number_of_adjusted_bombs.to_string(),
-                ResetColor.to_string()
-            )
-        } else {
-            number_of_adjusted_bombs.to_string()
-        }
+            self.number_colors.is_some() && (!selected || self.colored_numbers_on_selection);
+
+        number_of_adjusted_bombs
+            .to_string()
+            .chars()
+            .map(|digit| {
+                if use_color {
+                    let number_colors = self.number_colors.unwrap();
+                    format!(
+                        "{}{}{}",
+                        (match number_of_adjusted_bombs {
+                            1 => SetForegroundColor(number_colors[0]).to_string(),
+                            2 => SetForegroundColor(number_colors[1]).to_string(),
+                            3 => SetForegroundColor(number_colors[2]).to_string(),
+                            4 => SetForegroundColor(number_colors[3]).to_string(),
+                            5 => SetForegroundColor(number_colors[4]).to_string(),
+                            6..=8 => SetForegroundColor(number_colors[5]).to_string(),
+                            // the larger numbers of the wide neighbourhoods go round the colours again
+                            _ => SetForegroundColor(
+                                number_colors[(number_of_adjusted_bombs - 1) % 6],
+                            )
+                            .to_string(),
+                        }),
+                        digit,
+                        ResetColor
+                    )
+                } else {
+                    digit.to_string()
+                }
+            })
+            .collect()
     }
 
     // Returns a colored vertical border string, using yellow if selected, otherwise theme color
@@ -219,6 +587,22 @@ impl Theme {
         self.format_border(&self.line_horizontal, selected)
     }
 
+    pub fn format_outer_vertical_border(&self, selected: bool, wrap: bool) -> String {
+        if wrap {
+            self.format_border(&self.wrap_line_vertical, selected)
+        } else {
+            self.format_vertical_border(selected)
+        }
+    }
+
+    pub fn format_outer_horizontal_border(&self, selected: bool, wrap: bool) -> String {
+        if wrap {
+            self.format_border(&self.wrap_line_horizontal, selected)
+        } else {
+            self.format_horizontal_border(selected)
+        }
+    }
+
     pub fn format_cross(&self, selected: bool) -> String {
         self.format_border(
             &self.line_cross,
@@ -226,44 +610,70 @@ impl Theme {
         )
     }
 
-    pub fn format_corner_top_left(&self, selected: bool) -> String {
+    pub fn format_corner_top_left(&self, selected: bool, wrap: bool) -> String {
         self.format_border(
-            &self.corner_top_left,
+            if wrap {
+                &self.wrap_corner
+            } else {
+                &self.corner_top_left
+            },
             self.highlight_corner_on_selection && selected,
         )
     }
 
-    pub fn format_corner_top_right(&self, selected: bool) -> String {
+    pub fn format_corner_top_right(&self, selected: bool, wrap: bool) -> String {
         self.format_border(
-            &self.corner_top_right,
+            if wrap {
+                &self.wrap_corner
+            } else {
+                &self.corner_top_right
+            },
             self.highlight_corner_on_selection && selected,
         )
     }
 
-    pub fn format_corner_bottom_left(&self, selected: bool) -> String {
+    pub fn format_corner_bottom_left(&self, selected: bool, wrap: bool) -> String {
         self.format_border(
-            &self.corner_bottom_left,
+            if wrap {
+                &self.wrap_corner
+            } else {
+                &self.corner_bottom_left
+            },
             self.highlight_corner_on_selection && selected,
         )
     }
 
-    pub fn format_corner_bottom_right(&self, selected: bool) -> String {
+    pub fn format_corner_bottom_right(&self, selected: bool, wrap: bool) -> String {
         self.format_border(
-            &self.corner_bottom_right,
+            if wrap {
+                &self.wrap_corner
+            } else {
+                &self.corner_bottom_right
+            },
             self.highlight_corner_on_selection && selected,
         )
     }
 
-    pub fn format_edge_top(&self, selected: bool) -> String {
+    // a theme without inner borders still needs the edge to go on when it wraps
+    pub fn format_edge_top(&self, selected: bool, wrap: bool) -> String {
         self.format_border(
-            &self.edge_top,
+            if wrap && self.edge_top.is_empty() {
+                &self.wrap_line_horizontal
+            } else {
+                &self.edge_top
+            },
             self.highlight_corner_on_selection && selected,
         )
     }
 
-    pub fn format_edge_bottom(&self, selected: bool) -> String {
+    // a theme without inner borders still needs the edge to go on when it wraps
+    pub fn format_edge_bottom(&self, selected: bool, wrap: bool) -> String {
         self.format_border(
-            &self.edge_bottom,
+            if wrap && self.edge_bottom.is_empty() {
+                &self.wrap_line_horizontal
+            } else {
+                &self.edge_bottom
+            },
             self.highlight_corner_on_selection && selected,
         )
     }
@@ -283,7 +693,9 @@ impl Theme {
     }
 
     fn format_border(&self, symbol: &str, selected: bool) -> String {
-        if selected {
+        if symbol.is_empty() {
+            String::new()
+        } else if selected {
             format!(
                 "{}{}{}",
                 SetForegroundColor(Color::Yellow),
@@ -297,6 +709,22 @@ impl Theme {
         }
     }
 
+    // the unknown cell in a colour from green for no chance of a bomb to red for a sure bomb
+    pub fn format_bomb_probability(&self, probability: f64, width: usize) -> Vec<String> {
+        let color = Color::Rgb {
+            r: (255.0 * probability).round() as u8,
+            g: (255.0 * (1.0 - probability)).round() as u8,
+            b: 0,
+        };
+        let piece = format!(
+            "{}{}{}",
+            SetForegroundColor(color),
+            self.unknown,
+            ResetColor
+        );
+        vec![piece; width]
+    }
+
     // Returns a colored cell content string, using yellow if selected, otherwise normal
     pub fn format_cell_content(&self, content: &str, selected: bool) -> String {
         if selected {
@@ -311,3 +739,84 @@ impl Theme {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn theme_file_sets_fields_over_its_base() {
+        let theme = parse_theme(
+            "ocean",
+            r##"
+base = "borderless"
+unknown = "░"
+flag = "⚑"
+number_colors = ["blue", "#69c9fa", "green", "dark_cyan", "red", "magenta"]
+line_color = "dark_grey"
+"##,
+        )
+        .unwrap();
+        assert_eq!(theme.name, "ocean");
+        assert_eq!(theme.unknown, "░");
+        assert_eq!(theme.flag, "⚑");
+        assert!(!theme.outer_border_enabled);
+        assert_eq!(
+            theme.number_colors.unwrap()[1],
+            Color::Rgb {
+                r: 0x69,
+                g: 0xc9,
+                b: 0xfa
+            }
+        );
+        assert_eq!(theme.line_color, Some(Color::DarkGrey));
+
+        let theme = parse_theme("plain", "number_colors = []\nline_color = \"none\"").unwrap();
+        assert_eq!(theme.number_colors, None);
+        assert_eq!(theme.line_color, None);
+    }
+
+    #[test]
+    fn theme_file_errors_name_the_field() {
+        let error = |content: &str| parse_theme("wrong", content).err().unwrap();
+        assert_eq!(
+            error("bomb = \"💣\""),
+            "field `bomb` has \"💣\" which takes 2 columns, only glyphs of one column are supported"
+        );
+        assert_eq!(
+            error("corner_top_left = \"\""),
+            "field `corner_top_left` is empty, but the theme draws it, set it to a glyph of one column"
+        );
+        assert_eq!(
+            error("number_colors = [\"blue\"]"),
+            "field `number_colors` has 1 colours, it needs 6 or none"
+        );
+        assert!(error("line_color = \"purple\"")
+            .starts_with("field `line_color` has an unknown colour"));
+        assert!(error("bomb_glyph = \"B\"").contains("bomb_glyph"));
+        assert!(error("outer_border_enabled = \"yes\"").contains("outer_border_enabled"));
+        assert!(error("base = \"ocean\"").starts_with("field `base` has an unknown theme ocean"));
+    }
+
+    #[test]
+    fn theme_names_stay_in_the_themes_directory() {
+        for name in ["../../x", "a/b", "a\\b", ".."] {
+            assert!(load_theme(name).err().unwrap().contains("has a path in it"));
+        }
+    }
+
+    #[test]
+    fn every_built_in_theme_is_a_base() {
+        for name in BUILT_IN_THEME_NAMES {
+            let theme = parse_theme("file", &format!("base = \"{}\"", name)).unwrap();
+            let built_in = get_theme(name).unwrap();
+            assert_eq!(theme.flag, built_in.flag);
+            assert_eq!(theme.flag_background_color, built_in.flag_background_color);
+        }
+        let theme = parse_theme(
+            "file",
+            "base = \"dark_border\"\nflag_background_color = \"none\"",
+        );
+        assert_eq!(theme.unwrap().flag_background_color, None);
+    }
+}